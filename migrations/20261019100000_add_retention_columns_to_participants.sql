@@ -0,0 +1,3 @@
+ALTER TABLE participants
+ADD COLUMN last_interaction_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+ADD COLUMN retention_warning_sent_at timestamp DEFAULT NULL;
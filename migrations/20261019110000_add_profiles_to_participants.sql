@@ -0,0 +1,20 @@
+-- A Telegram chat can manage several participant profiles, e.g. to sign up a friend.
+-- The main profile of a chat keeps the chat id as its id. Additional profiles get negative ids
+-- from their own sequence, so that they never collide with (positive) private chat ids.
+BEGIN;
+
+ALTER TABLE participants
+ADD COLUMN chat_id bigint,
+ADD COLUMN profile_name text DEFAULT NULL,
+ADD COLUMN selected boolean NOT NULL DEFAULT TRUE;
+
+UPDATE participants SET chat_id = id;
+
+ALTER TABLE participants
+ALTER COLUMN chat_id SET NOT NULL;
+
+CREATE INDEX participants_chat_id_idx ON participants (chat_id);
+
+CREATE SEQUENCE participant_profile_id_seq INCREMENT BY -1 START WITH -1 MAXVALUE -1;
+
+COMMIT;
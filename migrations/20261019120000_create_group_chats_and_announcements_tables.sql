@@ -0,0 +1,20 @@
+CREATE TABLE group_chats (
+    chat_id bigint primary key
+);
+
+CREATE TABLE announcements (
+    chat_id bigint references group_chats(chat_id) on delete cascade not null,
+    course_id bigint references courses(id) on delete cascade not null,
+    message_id integer not null,
+    UNIQUE(chat_id, course_id)
+);
+
+CREATE TABLE announcement_signups (
+    chat_id bigint not null,
+    course_id bigint not null,
+    participant_id bigint references participants(id) on delete cascade not null,
+    display_name text not null,
+    created_at timestamp not null default (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (chat_id, course_id) REFERENCES announcements(chat_id, course_id) on delete cascade,
+    UNIQUE(chat_id, course_id, participant_id)
+);
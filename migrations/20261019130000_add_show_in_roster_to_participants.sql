@@ -0,0 +1,2 @@
+ALTER TABLE participants
+ADD COLUMN show_in_roster BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+CREATE TABLE reminders (
+    participant_id bigint references participants(id) on delete cascade not null,
+    course_id bigint references courses(id) on delete cascade not null,
+    remind_at timestamp not null,
+    UNIQUE(participant_id, course_id)
+);
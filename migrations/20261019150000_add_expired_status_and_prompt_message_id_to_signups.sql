@@ -0,0 +1,3 @@
+ALTER TYPE signup_status ADD VALUE 'Expired';
+
+ALTER TABLE signups ADD COLUMN prompt_message_id integer;
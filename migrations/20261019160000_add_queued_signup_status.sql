@@ -0,0 +1 @@
+ALTER TYPE signup_status ADD VALUE 'Queued';
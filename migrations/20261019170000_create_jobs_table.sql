@@ -0,0 +1,18 @@
+CREATE TABLE jobs (
+    id bigserial primary key,
+    job jsonb not null,
+    run_at timestamp not null,
+    attempts integer not null default 0,
+    locked_until timestamp,
+    failed_at timestamp,
+    last_error text,
+    created_at timestamp not null default (now() AT TIME ZONE 'UTC')
+);
+
+CREATE INDEX jobs_run_at_idx ON jobs (run_at) WHERE failed_at IS NULL;
+
+INSERT INTO jobs (job, run_at)
+SELECT jsonb_build_object('type', 'Remind', 'participant_id', participant_id, 'course_id', course_id), remind_at
+FROM reminders;
+
+DROP TABLE reminders;
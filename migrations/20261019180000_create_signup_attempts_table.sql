@@ -0,0 +1,11 @@
+CREATE TABLE signup_attempts (
+    id bigserial primary key,
+    participant_id bigint references participants(id) on delete cascade not null,
+    course_id bigint references courses(id) on delete cascade not null,
+    started_at timestamp not null default (now() AT TIME ZONE 'UTC'),
+    finished_at timestamp,
+    success boolean,
+    error text
+);
+
+CREATE INDEX signup_attempts_course_id_idx ON signup_attempts (course_id, started_at);
@@ -0,0 +1,4 @@
+-- Existing participants get the time of the migration, so they keep their order by id among
+-- each other and come before everyone who signs up later.
+ALTER TABLE participants
+ADD COLUMN created_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC');
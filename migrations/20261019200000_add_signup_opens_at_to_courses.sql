@@ -0,0 +1,2 @@
+ALTER TABLE courses
+ADD COLUMN signup_opens_at timestamp DEFAULT NULL;
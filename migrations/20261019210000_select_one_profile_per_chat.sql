@@ -0,0 +1,23 @@
+-- Only one profile per chat can be selected. New additional profiles are not selected until
+-- they are chosen explicitly.
+BEGIN;
+
+ALTER TABLE participants
+ALTER COLUMN selected SET DEFAULT FALSE;
+
+-- Keep the main profile selected where several profiles of a chat were selected.
+UPDATE participants
+SET selected = FALSE
+WHERE id IN (
+    SELECT id
+    FROM (
+        SELECT id, row_number() OVER (PARTITION BY chat_id ORDER BY id = chat_id DESC, id) AS position
+        FROM participants
+        WHERE selected
+    ) selected_profiles
+    WHERE position > 1
+);
+
+CREATE UNIQUE INDEX participants_selected_idx ON participants (chat_id) WHERE selected;
+
+COMMIT;
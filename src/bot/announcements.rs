@@ -0,0 +1,88 @@
+use crate::{
+    bot::{keyboards, text_messages::TextMessage},
+    models::{
+        course::Course,
+        group::{Announcement, Group},
+        participant::Participant,
+    },
+};
+use chrono_tz::Tz;
+use color_eyre::Result;
+use sqlx::{Pool, Postgres};
+use teloxide::{prelude::*, types::MessageId};
+
+/// Posts the announcement of the course in all registered group chats that did not get it yet.
+pub async fn send(bot: &Bot, pool: &Pool<Postgres>, course: &Course, timezone: Tz) -> Result<()> {
+    for group in Group::all(pool).await? {
+        if group.announcement(pool, course.id).await?.is_some() {
+            continue;
+        }
+        log::info!("announcing course {} in group {}", course.id, group.chat_id);
+        match bot
+            .send_message(
+                ChatId(group.chat_id),
+                TextMessage::GroupAnnouncement(course.clone(), timezone, vec![]).to_string(),
+            )
+            .reply_markup(keyboards::group_signup(course.id))
+            .await
+        {
+            Ok(msg) => {
+                Announcement {
+                    chat_id: group.chat_id,
+                    course_id: course.id,
+                    message_id: msg.id.0,
+                }
+                .create(pool)
+                .await?;
+            }
+            Err(e) => {
+                log::error!(
+                    "failed to announce course in group {}: {}",
+                    group.chat_id,
+                    e
+                );
+                if e.to_string().contains("bot was kicked")
+                    || e.to_string().contains("chat not found")
+                {
+                    log::info!(
+                        "bot is no member of group {} anymore, deleting the group now",
+                        group.chat_id
+                    );
+                    group.delete(pool).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Edits the announcements of the course so that they list everybody who is signed up for it.
+/// Announcements that cannot be edited are skipped, since the signups themselves succeeded.
+pub async fn refresh(bot: &Bot, pool: &Pool<Postgres>, course_id: i64, timezone: Tz) -> Result<()> {
+    let Some(course) = Course::find_by_id(pool, course_id).await? else {
+        return Ok(());
+    };
+    let names: Vec<Option<String>> = Participant::roster(pool, course_id)
+        .await?
+        .iter()
+        .map(Participant::roster_name)
+        .collect();
+    for announcement in Announcement::for_course(pool, course_id).await? {
+        if let Err(e) = bot
+            .edit_message_text(
+                ChatId(announcement.chat_id),
+                MessageId(announcement.message_id),
+                TextMessage::GroupAnnouncement(course.clone(), timezone, names.clone()).to_string(),
+            )
+            .reply_markup(keyboards::group_signup(course.id))
+            .await
+        {
+            log::error!(
+                "failed to refresh announcement in group {}: {}",
+                announcement.chat_id,
+                e
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::models::{gender::Gender, reminder::Delay, signup::Selection, status::Status};
+use std::{fmt::Display, str::FromStr};
+
+/// The data attached to inline keyboard buttons. Telegram limits it to 64 bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallbackData {
+    Gender(Gender),
+    Status(Status),
+    NoAnswer,
+    SignupAccept(i64, Selection),
+    SignupReject(i64),
+    SignupRemind(i64),
+    SignupSnooze(i64, Delay),
+    GroupSignup(i64),
+    Roster(i64),
+}
+
+impl Display for CallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gender(gender) => write!(f, "gender:{}", gender.as_ref()),
+            Self::Status(status) => write!(f, "status:{}", status.as_ref()),
+            Self::NoAnswer => write!(f, "no answer"),
+            Self::SignupAccept(course_id, Selection::Me) => write!(f, "signup:{course_id}:me"),
+            Self::SignupAccept(course_id, Selection::Profile(id)) => {
+                write!(f, "signup:{course_id}:profile:{id}")
+            }
+            Self::SignupAccept(course_id, Selection::All) => write!(f, "signup:{course_id}:all"),
+            Self::SignupReject(course_id) => write!(f, "signup:{course_id}:reject"),
+            Self::SignupRemind(course_id) => write!(f, "signup:{course_id}:remind"),
+            Self::SignupSnooze(course_id, delay) => {
+                write!(f, "snooze:{course_id}:{}", delay.as_ref())
+            }
+            Self::GroupSignup(course_id) => write!(f, "group_signup:{course_id}"),
+            Self::Roster(course_id) => write!(f, "roster:{course_id}"),
+        }
+    }
+}
+
+impl FromStr for CallbackData {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let course_id = || parts.get(1).and_then(|id| id.parse().ok()).ok_or(());
+        match parts.as_slice() {
+            ["gender", gender] => Ok(Self::Gender(gender.parse().map_err(|_| ())?)),
+            ["status", status] => Ok(Self::Status(status.parse().map_err(|_| ())?)),
+            ["no answer"] => Ok(Self::NoAnswer),
+            ["signup", _, "me"] => Ok(Self::SignupAccept(course_id()?, Selection::Me)),
+            ["signup", _, "profile", id] => Ok(Self::SignupAccept(
+                course_id()?,
+                Selection::Profile(id.parse().map_err(|_| ())?),
+            )),
+            ["signup", _, "all"] => Ok(Self::SignupAccept(course_id()?, Selection::All)),
+            ["signup", _, "reject"] => Ok(Self::SignupReject(course_id()?)),
+            ["signup", _, "remind"] => Ok(Self::SignupRemind(course_id()?)),
+            ["snooze", _, delay] => Ok(Self::SignupSnooze(
+                course_id()?,
+                delay.parse().map_err(|_| ())?,
+            )),
+            ["group_signup", _] => Ok(Self::GroupSignup(course_id()?)),
+            ["roster", _] => Ok(Self::Roster(course_id()?)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CallbackData;
+    use crate::models::{gender::Gender, reminder::Delay, signup::Selection, status::Status};
+    use strum::IntoEnumIterator;
+
+    /// Every kind of callback data, with the longest ids possible.
+    fn all() -> Vec<CallbackData> {
+        let id = i64::MIN;
+        let mut all = vec![
+            CallbackData::NoAnswer,
+            CallbackData::SignupAccept(id, Selection::Me),
+            CallbackData::SignupAccept(id, Selection::Profile(id)),
+            CallbackData::SignupAccept(id, Selection::All),
+            CallbackData::SignupReject(id),
+            CallbackData::SignupRemind(id),
+            CallbackData::GroupSignup(id),
+            CallbackData::Roster(id),
+        ];
+        all.extend(Gender::iter().map(CallbackData::Gender));
+        all.extend(Status::iter().map(CallbackData::Status));
+        all.extend(Delay::iter().map(|delay| CallbackData::SignupSnooze(id, delay)));
+        all
+    }
+
+    #[test]
+    fn round_trips() {
+        for data in all() {
+            assert_eq!(data.to_string().parse(), Ok(data.clone()), "{data}");
+        }
+    }
+
+    #[test]
+    fn fits_into_64_bytes() {
+        for data in all() {
+            assert!(data.to_string().len() <= 64, "{data}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_data() {
+        for text in [
+            "",
+            "signup",
+            "signup:abc:me",
+            "signup:1:maybe",
+            "gender:Unbekannt",
+            "snooze:1:forever",
+            "roster:1:2",
+        ] {
+            assert_eq!(text.parse::<CallbackData>(), Err(()), "{text}");
+        }
+    }
+}
@@ -4,7 +4,12 @@ use crate::{
         schema::{MyDialogue, State},
         text_messages::TextMessage,
     },
-    models::{course::Course, participant::Participant},
+    config::AppConfig,
+    models::{
+        course::Course,
+        participant::Participant,
+        signup::{self, Signup},
+    },
 };
 use color_eyre::{eyre::eyre, Result};
 use sqlx::{Pool, Postgres};
@@ -21,20 +26,71 @@ pub async fn state(dialogue: &MyDialogue) -> Result<State> {
         .ok_or_else(|| eyre!("no state found"))
 }
 
+/// Resets the dialogue and delivers a signup prompt that was queued while the participant was
+/// editing their data.
+pub async fn reset(
+    bot: &Bot,
+    dialogue: &MyDialogue,
+    pool: &Pool<Postgres>,
+    config: &AppConfig,
+) -> Result<()> {
+    dialogue.reset().await.map_err(|e| eyre!(e))?;
+    deliver_queued(bot, dialogue, pool, config).await
+}
+
+pub async fn deliver_queued(
+    bot: &Bot,
+    dialogue: &MyDialogue,
+    pool: &Pool<Postgres>,
+    config: &AppConfig,
+) -> Result<()> {
+    for signup in Signup::queued(pool, Some(dialogue.chat_id().0)).await? {
+        log::info!(
+            "delivering queued signup prompt for course {} to chat {}",
+            signup.course_id,
+            dialogue.chat_id()
+        );
+        let participant = Participant::find_by_id(pool, signup.participant_id).await?;
+        participant
+            .set_signup_status(pool, signup.course_id, signup::Status::Notified)
+            .await?;
+        let missing_fields = participant.missing_fields();
+        if missing_fields.is_empty() {
+            // Also stores the prompt, so that it can be closed when the signup expires.
+            update(
+                State::ReceiveSignupResponse(signup.course_id),
+                bot.clone(),
+                dialogue.clone(),
+                pool,
+                config,
+            )
+            .await?;
+        } else {
+            bot.send_message(
+                dialogue.chat_id(),
+                TextMessage::IncompleteData(missing_fields).to_string(),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
 pub async fn update(
     mut new_state: State,
     bot: Bot,
     dialogue: MyDialogue,
     pool: &Pool<Postgres>,
+    config: &AppConfig,
 ) -> Result<()> {
-    let participant = Participant::find_by_id(pool, dialogue.chat_id().0).await?;
+    let participant = Participant::selected(pool, dialogue.chat_id().0).await?;
     let message: String = match new_state {
         State::Default => String::new(),
         State::ReceiveSignupResponse(course_id) => {
             let course = Course::find_by_id(pool, course_id)
                 .await?
                 .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
-            TextMessage::SignupResponse(course).to_string()
+            TextMessage::SignupResponse(course, config.timezone.0).to_string()
         }
         State::ReceiveGivenName(_) => "Bitte gib deinen Vornamen ein.".into(),
         State::ReceiveLastName(_) => "Bitte gib deinen Nachnamen ein.".into(),
@@ -63,8 +119,19 @@ pub async fn update(
             }
         }
         State::ReceiveDeleteConfirmation => {
-            "Bist du sicher? Antworte mit \"JA\", um deine Daten endgültig zu löschen.".into()
+            if participant.is_main_profile() {
+                "Bist du sicher? Antworte mit \"JA\", um deine Daten endgültig zu löschen. Dabei werden auch alle deine weiteren Profile gelöscht.".into()
+            } else {
+                format!(
+                    "Bist du sicher? Antworte mit \"JA\", um das Profil {} endgültig zu löschen.",
+                    participant.profile_label()
+                )
+            }
         }
+        State::ReceiveProfileName => {
+            "Wie soll das neue Profil heißen?\n\nBeispiel: Der Vorname der Person, die du mit anmelden möchtest.".into()
+        }
+        State::ReceiveProfileSelection => "Welches Profil möchtest du bearbeiten?".into(),
     };
 
     match new_state {
@@ -100,11 +167,24 @@ pub async fn update(
                 return Ok(());
             }
         }
-        State::ReceiveSignupResponse(_) => {
+        State::ReceiveProfileSelection => {
+            let profiles = Participant::profiles(pool, dialogue.chat_id().0).await?;
             bot.send_message(dialogue.chat_id(), message)
+                .reply_markup(keyboards::profiles(&profiles))
+                .await?;
+        }
+        State::ReceiveSignupResponse(course_id) => {
+            let profiles =
+                Participant::pending_profiles(pool, dialogue.chat_id().0, course_id).await?;
+            let msg = bot
+                .send_message(dialogue.chat_id(), message)
                 .parse_mode(teloxide::types::ParseMode::Html)
                 .message_effect_id(MessageEffect::Fire.id())
-                .reply_markup(keyboards::signup())
+                .reply_markup(keyboards::signup(course_id, &profiles))
+                .await?;
+            Participant::find_by_id(pool, dialogue.chat_id().0)
+                .await?
+                .set_signup_prompt(pool, course_id, msg.id.0)
                 .await?;
         }
         _ => {
@@ -1,31 +1,276 @@
 use crate::{
     bot::{
         dialogue_utils,
+        handlers::messages,
+        keyboards,
         schema::{MyDialogue, State},
+        text_messages::TextMessage,
     },
-    models::participant::Participant,
+    config::AppConfig,
+    models::{
+        course::Course, gender::Gender, job::Job, participant::Participant, reminder::Delay,
+        signup, status::Status,
+    },
+    utils::http::HttpClient,
 };
+use chrono::Utc;
 use color_eyre::{eyre::eyre, Result};
 use sqlx::{Pool, Postgres};
-use teloxide::prelude::*;
+use std::sync::Arc;
+use strum::{EnumProperty, IntoEnumIterator};
+use teloxide::{
+    prelude::*,
+    types::ParseMode,
+    utils::{html, render::RenderMessageTextHelper},
+};
 
-pub async fn receive_email_callback(
+/// Answers a callback query from a button that does not belong to the current state of the
+/// dialogue anymore and removes the buttons from its message.
+async fn answer_stale_callback(bot: &Bot, q: &CallbackQuery) -> Result<()> {
+    log::info!(
+        "answering stale callback query {} by user {}",
+        q.id,
+        q.from.id
+    );
+    bot.answer_callback_query(q.id.clone())
+        .text("Diese Auswahl ist nicht mehr aktuell.")
+        .await?;
+    if let Some(message) = q.regular_message() {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Replaces the buttons of the message the callback query originates from with the choice.
+async fn show_choice(bot: &Bot, q: &CallbackQuery, choice: &str) -> Result<()> {
+    bot.answer_callback_query(q.id.clone()).await?;
+    let Some(message) = q.regular_message() else {
+        return Ok(());
+    };
+    let text = message.html_text().unwrap_or_default();
+    bot.edit_message_text(
+        message.chat.id,
+        message.id,
+        format!("{text}\n\nAuswahl: <b>{}</b>", html::escape(choice)),
+    )
+    .parse_mode(ParseMode::Html)
+    .await?;
+    Ok(())
+}
+
+pub async fn receive_gender_callback(
     bot: Bot,
     dialogue: MyDialogue,
     q: CallbackQuery,
+    state: State,
+    gender: Gender,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
-    bot.answer_callback_query(q.id).await?;
+    log::info!("receive_gender_callback by chat {}", dialogue.chat_id());
+    if !matches!(state, State::ReceiveGender(_)) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    show_choice(
+        &bot,
+        &q,
+        gender
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop")),
+    )
+    .await?;
+    messages::set_gender(bot, dialogue, &pool, &config, gender).await
+}
+
+pub async fn receive_status_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    state: State,
+    status: Status,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_status_callback by chat {}", dialogue.chat_id());
+    if !matches!(state, State::ReceiveStatus(_)) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    show_choice(
+        &bot,
+        &q,
+        status
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop")),
+    )
+    .await?;
+    messages::set_status(bot, dialogue, &pool, &config, status).await
+}
 
-    let message_id = match dialogue_utils::state(&dialogue).await? {
-        State::ReceiveEmail(_, message_id) => message_id,
-        _ => None,
+pub async fn receive_signup_accept_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    (course_id, selection): (i64, signup::Selection),
+    pool: Pool<Postgres>,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!(
+        "receive_signup_accept_callback by chat {}",
+        dialogue.chat_id()
+    );
+    // The state is read here instead of being injected, to keep the number of arguments down.
+    let state = dialogue.get().await.map_err(|e| eyre!(e))?;
+    if !matches!(state, Some(State::ReceiveSignupResponse(id)) if id == course_id) {
+        return answer_stale_callback(&bot, &q).await;
     }
-    .ok_or_else(|| eyre!("not in state ReceiveEmail"))?;
+    let profiles = Participant::pending_profiles(&pool, dialogue.chat_id().0, course_id).await?;
+    let choice = if profiles.is_empty() {
+        signup::Request::Accept
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop"))
+            .to_string()
+    } else {
+        selection.label(&profiles)
+    };
+    show_choice(&bot, &q, &choice).await?;
+    messages::answer_signup(
+        bot,
+        dialogue,
+        &pool,
+        &http,
+        &config,
+        course_id,
+        Some(selection),
+    )
+    .await
+}
 
-    bot.edit_message_reply_markup(dialogue.chat_id(), message_id)
-        .await?;
-    let mut participant = Participant::find_by_id(&pool, dialogue.chat_id().0).await?;
+pub async fn receive_signup_reject_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    course_id: i64,
+    pool: Pool<Postgres>,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!(
+        "receive_signup_reject_callback by chat {}",
+        dialogue.chat_id()
+    );
+    let state = dialogue.get().await.map_err(|e| eyre!(e))?;
+    if !matches!(state, Some(State::ReceiveSignupResponse(id)) if id == course_id) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    show_choice(
+        &bot,
+        &q,
+        signup::Request::Reject
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop")),
+    )
+    .await?;
+    messages::answer_signup(bot, dialogue, &pool, &http, &config, course_id, None).await
+}
+
+pub async fn receive_signup_remind_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    state: State,
+    course_id: i64,
+    pool: Pool<Postgres>,
+) -> Result<()> {
+    log::info!(
+        "receive_signup_remind_callback by chat {}",
+        dialogue.chat_id()
+    );
+    if !matches!(state, State::ReceiveSignupResponse(id) if id == course_id) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    let course = Course::find_by_id(&pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    let now = Utc::now().naive_utc();
+    let delays: Vec<Delay> = Delay::iter()
+        .filter(|delay| delay.remind_at(&course, now).is_some())
+        .collect();
+    if delays.is_empty() {
+        bot.answer_callback_query(q.id)
+            .text("Der Kurs beginnt zu bald für eine Erinnerung.")
+            .await?;
+        return Ok(());
+    }
+    bot.answer_callback_query(q.id.clone()).await?;
+    if let Some(message) = q.regular_message() {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .reply_markup(keyboards::remind(course_id, &delays))
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn receive_signup_snooze_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    state: State,
+    (course_id, delay): (i64, Delay),
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!(
+        "receive_signup_snooze_callback by chat {}",
+        dialogue.chat_id()
+    );
+    if !matches!(state, State::ReceiveSignupResponse(id) if id == course_id) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    let course = Course::find_by_id(&pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    let Some(remind_at) = delay.remind_at(&course, Utc::now().naive_utc()) else {
+        bot.answer_callback_query(q.id)
+            .text("Der Kurs beginnt zu bald für diese Erinnerung.")
+            .await?;
+        return Ok(());
+    };
+    Job::Remind {
+        participant_id: dialogue.chat_id().0,
+        course_id,
+    }
+    .reschedule(&pool, remind_at)
+    .await?;
+    let time = remind_at
+        .and_utc()
+        .with_timezone(&config.timezone.0)
+        .format("%H:%M");
+    show_choice(&bot, &q, &format!("Erinnerung um {time} Uhr")).await?;
+    bot.send_message(
+        dialogue.chat_id(),
+        format!("Alles klar, ich frage dich um {time} Uhr noch einmal."),
+    )
+    .await?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    Ok(())
+}
+
+pub async fn receive_email_callback(
+    bot: Bot,
+    dialogue: MyDialogue,
+    q: CallbackQuery,
+    state: State,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_email_callback by chat {}", dialogue.chat_id());
+    if !matches!(state, State::ReceiveEmail(_, _)) {
+        return answer_stale_callback(&bot, &q).await;
+    }
+    show_choice(&bot, &q, "Keine Angabe.").await?;
+    let mut participant = Participant::selected(&pool, dialogue.chat_id().0).await?;
     if participant.email.is_some() {
         participant.email = None;
         participant.update(&pool).await?;
@@ -38,15 +283,38 @@ pub async fn receive_email_callback(
         )
         .await?;
     }
-    let state = dialogue_utils::state(&dialogue).await?;
     if state.is_in_dialogue() {
-        dialogue_utils::update(State::ReceiveStatus(true), bot, dialogue, &pool).await?;
+        dialogue_utils::update(State::ReceiveStatus(true), bot, dialogue, &pool, &config).await?;
     } else {
-        dialogue.reset().await.map_err(|e| eyre!(e))?;
+        dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     }
     Ok(())
 }
 
+pub async fn receive_roster_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    course_id: i64,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_roster_callback by user {}", q.from.id);
+    bot.answer_callback_query(q.id).await?;
+    let chat_id = q
+        .message
+        .as_ref()
+        .map(|msg| msg.chat().id)
+        .ok_or_else(|| eyre!("callback query without message"))?;
+    let participants = Participant::roster(&pool, course_id).await?;
+    let message = TextMessage::Roster(
+        participants,
+        config.signup.order,
+        config.signup.concurrency <= 1,
+    );
+    bot.send_message(chat_id, message.to_string()).await?;
+    Ok(())
+}
+
 pub async fn invalid_callback_query(
     bot: Bot,
     dialogue: MyDialogue,
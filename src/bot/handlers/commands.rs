@@ -4,36 +4,77 @@ use crate::{
         schema::{Command, MyDialogue, State},
         text_messages::TextMessage,
     },
-    models::{course::Course, participant::Participant, signup::Status},
+    config::AppConfig,
+    models::{
+        course::Course,
+        group::AnnouncementSignup,
+        job::ScheduledJob,
+        participant::Participant,
+        signup::{self, Status},
+    },
+    utils::http::HttpClient,
 };
 use color_eyre::{eyre::eyre, Result};
 use sqlx::{Pool, Postgres};
-use teloxide::{prelude::*, types::KeyboardRemove, utils::command::BotCommands};
+use std::sync::Arc;
+use teloxide::{
+    prelude::*,
+    types::{InputFile, KeyboardRemove},
+    utils::command::BotCommands,
+};
 
-pub async fn help(bot: Bot, dialogue: MyDialogue, msg: Message) -> Result<()> {
+pub async fn help(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("help by chat {}", msg.chat.id);
     bot.send_message(msg.chat.id, Command::descriptions().to_string())
         .reply_markup(KeyboardRemove::default())
         .await?;
-    dialogue.reset().await.map_err(|e| eyre!(e))?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn start(bot: Bot, dialogue: MyDialogue, msg: Message) -> Result<()> {
+pub async fn start(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("start by chat {}", msg.chat.id);
-    bot.send_message(msg.chat.id, TextMessage::Start.to_string())
+    let text = config
+        .texts
+        .start
+        .clone()
+        .unwrap_or_else(|| TextMessage::Start.to_string());
+    bot.send_message(msg.chat.id, text)
         .reply_markup(KeyboardRemove::default())
         .await?;
-    dialogue.reset().await.map_err(|e| eyre!(e))?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn cancel(bot: Bot, dialogue: MyDialogue, msg: Message) -> Result<()> {
+pub async fn cancel(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("cancel by chat {}", msg.chat.id);
-    bot.send_message(msg.chat.id, TextMessage::Cancel.to_string())
+    let text = config
+        .texts
+        .cancel
+        .clone()
+        .unwrap_or_else(|| TextMessage::Cancel.to_string());
+    bot.send_message(msg.chat.id, text)
         .reply_markup(KeyboardRemove::default())
         .await?;
-    dialogue.reset().await.map_err(|e| eyre!(e))?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     Ok(())
 }
 
@@ -42,16 +83,18 @@ pub async fn enter_data(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("enter_data by chat {}", msg.chat.id);
     if (Participant::find_by_id(&pool, msg.chat.id.0).await).is_err() {
         let participant = Participant {
             id: msg.chat.id.0,
+            chat_id: msg.chat.id.0,
             ..Default::default()
         };
         participant.create(&pool).await?;
     }
-    dialogue_utils::update(State::ReceiveGivenName(true), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveGivenName(true), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
@@ -60,14 +103,15 @@ pub async fn show_data(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("show_data by chat {}", msg.chat.id);
-    let participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
+    let participant = Participant::selected(&pool, msg.chat.id.0).await?;
     bot.send_message(msg.chat.id, TextMessage::ShowData(participant).to_string())
         .parse_mode(teloxide::types::ParseMode::Html)
         .reply_markup(KeyboardRemove::default())
         .await?;
-    dialogue.reset().await.map_err(|e| eyre!(e))?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     Ok(())
 }
 
@@ -76,36 +120,80 @@ pub async fn signup(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("signup by chat {}", msg.chat.id);
     if let Some(course) = Course::today(&pool).await? {
+        if course.has_started() {
+            bot.send_message(
+                msg.chat.id,
+                "Der heutige Kurs hat bereits begonnen, eine Anmeldung ist nicht mehr möglich.",
+            )
+            .reply_markup(KeyboardRemove::default())
+            .await?;
+            dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+            return Ok(());
+        }
         let participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-        if let Some(signup) = participant.signup(&pool, course.id).await? {
-            match signup.status {
-                Status::SignedUp => {
-                    bot.send_message(msg.chat.id, "Du bist bereits angemeldet. Um dich abzumelden, musst du beim UniSport anrufen.").await?;
-                }
-                _ => {
-                    dialogue_utils::update(
-                        State::ReceiveSignupResponse(course.id),
-                        bot,
-                        dialogue,
-                        &pool,
-                    )
+        let missing_fields = participant.missing_fields();
+        if !missing_fields.is_empty() {
+            bot.send_message(
+                msg.chat.id,
+                TextMessage::IncompleteData(missing_fields).to_string(),
+            )
+            .reply_markup(KeyboardRemove::default())
+            .await?;
+            dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+            return Ok(());
+        }
+        let mut offered_profiles = 0;
+        for profile in Participant::profiles(&pool, msg.chat.id.0).await? {
+            if profile.is_main_profile() || !profile.missing_fields().is_empty() {
+                continue;
+            }
+            let signed_up = profile
+                .signup(&pool, course.id)
+                .await?
+                .is_some_and(|signup| matches!(signup.status, Status::SignedUp));
+            if !signed_up {
+                profile
+                    .set_signup_status(&pool, course.id, Status::Notified)
                     .await?;
+                offered_profiles += 1;
+            }
+        }
+        match participant.signup(&pool, course.id).await? {
+            Some(signup) if matches!(signup.status, Status::SignedUp) && offered_profiles == 0 => {
+                bot.send_message(msg.chat.id, "Du bist bereits angemeldet. Um dich abzumelden, musst du beim UniSport anrufen.").await?;
+            }
+            Some(signup) => {
+                if !matches!(signup.status, Status::SignedUp) {
+                    participant
+                        .set_signup_status(&pool, course.id, Status::Notified)
+                        .await?;
                 }
+                dialogue_utils::update(
+                    State::ReceiveSignupResponse(course.id),
+                    bot,
+                    dialogue,
+                    &pool,
+                    &config,
+                )
+                .await?;
             }
-        } else {
-            dialogue_utils::update(
-                State::ReceiveSignupResponse(course.id),
-                bot,
-                dialogue,
-                &pool,
-            )
-            .await?;
-            participant
-                .set_signup_status(&pool, course.id, Status::Notified)
+            None => {
+                participant
+                    .set_signup_status(&pool, course.id, Status::Notified)
+                    .await?;
+                dialogue_utils::update(
+                    State::ReceiveSignupResponse(course.id),
+                    bot,
+                    dialogue,
+                    &pool,
+                    &config,
+                )
                 .await?;
+            }
         }
     } else {
         bot.send_message(
@@ -114,68 +202,365 @@ pub async fn signup(
         )
         .reply_markup(KeyboardRemove::default())
         .await?;
-        dialogue.reset().await.map_err(|e| eyre!(e))?;
+        dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    };
+
+    Ok(())
+}
+
+pub async fn check_data(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("check_data by chat {}", msg.chat.id);
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    let participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    let missing_fields = participant.missing_fields();
+    if !missing_fields.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            TextMessage::IncompleteData(missing_fields).to_string(),
+        )
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+        return Ok(());
+    }
+    let course = match Course::today(&pool).await? {
+        Some(course) => Some(course),
+        None => Course::next(&pool).await?,
+    };
+    let Some(course) = course else {
+        bot.send_message(
+            msg.chat.id,
+            "Ich habe keinen anstehenden Kurs gefunden, mit dem ich deine Daten prüfen könnte.",
+        )
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+        return Ok(());
     };
+    bot.send_message(
+        msg.chat.id,
+        "Ok, ich schicke deine Daten zur Probe an den UniSport, ohne dich anzumelden. Einen Moment bitte...",
+    )
+    .reply_markup(KeyboardRemove::default())
+    .await?;
+    match signup::check(&http, &participant, course.id).await {
+        Ok(()) => {
+            bot.send_message(
+                msg.chat.id,
+                "Der UniSport hat deine Daten akzeptiert. Du wurdest <u>nicht</u> angemeldet.",
+            )
+            .parse_mode(teloxide::types::ParseMode::Html)
+            .await?;
+        }
+        Err(err) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("Fehler bei der Prüfung:\n\n{err}\n\nNutze /show_data, um deine Daten zu ändern. Beachte, dass ich deine Daten erst prüfen kann, wenn die Anmeldung für den Kurs geöffnet ist."),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
 
+pub async fn export(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+) -> Result<()> {
+    log::info!("export by chat {}", msg.chat.id);
+    let dialogue_state = dialogue.get().await.map_err(|e| eyre!(e))?;
+    let participants = Participant::profiles(&pool, msg.chat.id.0).await?;
+    if participants.is_empty() {
+        bot.send_message(msg.chat.id, "Ich habe keine Daten über dich gespeichert.")
+            .reply_markup(KeyboardRemove::default())
+            .await?;
+        return Ok(());
+    }
+    let mut profiles = vec![];
+    for participant in participants {
+        let metadata = participant.metadata(&pool).await?;
+        let signups = participant.signups_with_courses(&pool).await?;
+        let signup_attempts = signup::Attempt::for_participant(&pool, participant.id).await?;
+        let announcement_signups =
+            AnnouncementSignup::for_participant(&pool, participant.id).await?;
+        let jobs = ScheduledJob::for_participant(&pool, participant.id).await?;
+        profiles.push(serde_json::json!({
+            "participant": participant,
+            "metadata": metadata,
+            "signups": signups,
+            "signup_attempts": signup_attempts,
+            "announcement_signups": announcement_signups,
+            "jobs": jobs,
+        }));
+    }
+    let export = serde_json::json!({
+        "profiles": profiles,
+        "dialogue_state": dialogue_state,
+    });
+    bot.send_document(
+        msg.chat.id,
+        InputFile::memory(serde_json::to_vec_pretty(&export)?).file_name("daten.json"),
+    )
+    .caption("Das sind alle Daten, die ich über dich gespeichert habe.")
+    .reply_markup(KeyboardRemove::default())
+    .await?;
     Ok(())
 }
 
-pub async fn delete(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn profiles(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("profiles by chat {}", msg.chat.id);
+    let profiles = Participant::profiles(&pool, msg.chat.id.0).await?;
+    let selected = Participant::selected(&pool, msg.chat.id.0).await?;
+    bot.send_message(
+        msg.chat.id,
+        TextMessage::Profiles(profiles, selected.id).to_string(),
+    )
+    .reply_markup(KeyboardRemove::default())
+    .await?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    Ok(())
+}
+
+pub async fn add_profile(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("add_profile by chat {}", msg.chat.id);
+    if Participant::find_by_id(&pool, msg.chat.id.0).await.is_err() {
+        bot.send_message(
+            msg.chat.id,
+            "Bitte gib zuerst deine eigenen Daten mit /enter_data ein.",
+        )
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+        dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+        return Ok(());
+    }
+    dialogue_utils::update(State::ReceiveProfileName, bot, dialogue, &pool, &config).await?;
+    Ok(())
+}
+
+pub async fn switch_profile(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("switch_profile by chat {}", dialogue.chat_id());
+    dialogue_utils::update(
+        State::ReceiveProfileSelection,
+        bot,
+        dialogue,
+        &pool,
+        &config,
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn wer(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("wer by chat {}", msg.chat.id);
+    if let Some(course) = Course::today(&pool).await? {
+        let participants = Participant::roster(&pool, course.id).await?;
+        let message = TextMessage::Roster(
+            participants,
+            config.signup.order,
+            config.signup.concurrency <= 1,
+        );
+        bot.send_message(msg.chat.id, message.to_string())
+            .reply_markup(KeyboardRemove::default())
+            .await?;
+    } else {
+        bot.send_message(
+            msg.chat.id,
+            "Für heute habe ich leider keine Kurse gefunden.",
+        )
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+    }
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    Ok(())
+}
+
+pub async fn edit_show_in_roster(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("edit_show_in_roster by chat {}", msg.chat.id);
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    let show = !participant.show_in_roster;
+    participant.set_show_in_roster(&pool, show).await?;
+    let text = if show {
+        "Dein Vorname und der erste Buchstabe deines Nachnamens werden ab jetzt bei /wer angezeigt."
+    } else {
+        "Du wirst bei /wer ab jetzt nicht mehr namentlich angezeigt."
+    };
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+    dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+    Ok(())
+}
+
+pub async fn delete(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("delete by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveDeleteConfirmation, bot, dialogue, &pool).await?;
+    dialogue_utils::update(
+        State::ReceiveDeleteConfirmation,
+        bot,
+        dialogue,
+        &pool,
+        &config,
+    )
+    .await?;
     Ok(())
 }
 
-pub async fn edit_given_name(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_given_name(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_given_name by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveGivenName(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(
+        State::ReceiveGivenName(false),
+        bot,
+        dialogue,
+        &pool,
+        &config,
+    )
+    .await?;
     Ok(())
 }
 
-pub async fn edit_last_name(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_last_name(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_last_name by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveLastName(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveLastName(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_gender(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_gender(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_gender by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveGender(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveGender(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_street(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_street(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_street by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveStreet(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveStreet(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_city(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_city(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_city by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveCity(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveCity(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_phone(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_phone(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_phone by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceivePhone(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceivePhone(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_email(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_email(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_email by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveEmail(false, None), bot, dialogue, &pool).await?;
+    dialogue_utils::update(
+        State::ReceiveEmail(false, None),
+        bot,
+        dialogue,
+        &pool,
+        &config,
+    )
+    .await?;
     Ok(())
 }
 
-pub async fn edit_status(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_status(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_status by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveStatus(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(State::ReceiveStatus(false), bot, dialogue, &pool, &config).await?;
     Ok(())
 }
 
-pub async fn edit_status_info(bot: Bot, dialogue: MyDialogue, pool: Pool<Postgres>) -> Result<()> {
+pub async fn edit_status_info(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
     log::info!("edit_status_info by chat {}", dialogue.chat_id());
-    dialogue_utils::update(State::ReceiveStatusInfo(false), bot, dialogue, &pool).await?;
+    dialogue_utils::update(
+        State::ReceiveStatusInfo(false),
+        bot,
+        dialogue,
+        &pool,
+        &config,
+    )
+    .await?;
     Ok(())
 }
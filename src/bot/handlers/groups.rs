@@ -0,0 +1,146 @@
+use crate::{
+    bot::handlers::sign_up,
+    config::AppConfig,
+    models::{
+        course::Course,
+        group::Group,
+        participant::Participant,
+        signup::{self, Status},
+    },
+    utils::http::HttpClient,
+};
+use color_eyre::{eyre::eyre, Result};
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use teloxide::prelude::*;
+
+const ADMIN_ONLY: &str = "Das dürfen nur Admins der Gruppe.";
+
+/// Whether the sender of the message is an administrator of the group. Anonymous administrators
+/// send messages on behalf of the group itself.
+async fn sent_by_admin(bot: &Bot, msg: &Message) -> Result<bool> {
+    if msg
+        .sender_chat
+        .as_ref()
+        .is_some_and(|chat| chat.id == msg.chat.id)
+    {
+        return Ok(true);
+    }
+    let Some(user) = &msg.from else {
+        return Ok(false);
+    };
+    Ok(bot
+        .get_chat_member(msg.chat.id, user.id)
+        .await?
+        .is_privileged())
+}
+
+pub async fn register_group(bot: Bot, msg: Message, pool: Pool<Postgres>) -> Result<()> {
+    log::info!("register_group by chat {}", msg.chat.id);
+    if !sent_by_admin(&bot, &msg).await? {
+        bot.send_message(msg.chat.id, ADMIN_ONLY).await?;
+        return Ok(());
+    }
+    Group {
+        chat_id: msg.chat.id.0,
+    }
+    .create(&pool)
+    .await?;
+    bot.send_message(
+        msg.chat.id,
+        "Ab jetzt kündige ich die Kurse hier an. Wer mir privat seine Daten gegeben hat, kann sich dann direkt hier anmelden.",
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn unregister_group(bot: Bot, msg: Message, pool: Pool<Postgres>) -> Result<()> {
+    log::info!("unregister_group by chat {}", msg.chat.id);
+    if !sent_by_admin(&bot, &msg).await? {
+        bot.send_message(msg.chat.id, ADMIN_ONLY).await?;
+        return Ok(());
+    }
+    Group {
+        chat_id: msg.chat.id.0,
+    }
+    .delete(&pool)
+    .await?;
+    bot.send_message(msg.chat.id, "Ich kündige hier keine Kurse mehr an.")
+        .await?;
+    Ok(())
+}
+
+pub async fn receive_group_signup_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    course_id: i64,
+    pool: Pool<Postgres>,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_group_signup_callback by user {}", q.from.id);
+    let chat_id = q
+        .message
+        .as_ref()
+        .map(|msg| msg.chat().id)
+        .ok_or_else(|| eyre!("callback query without message"))?;
+    let group = Group { chat_id: chat_id.0 };
+    let (Some(announcement), Some(course)) = (
+        group.announcement(&pool, course_id).await?,
+        Course::find_by_id(&pool, course_id).await?,
+    ) else {
+        bot.answer_callback_query(q.id)
+            .text("Diese Ankündigung ist nicht mehr aktuell.")
+            .await?;
+        return Ok(());
+    };
+    if course.has_started() {
+        bot.answer_callback_query(q.id)
+            .text("Dieser Kurs hat bereits begonnen, eine Anmeldung ist nicht mehr möglich.")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    }
+    // Private chat ids are equal to the user ids, so this is the main profile of the user.
+    let Ok(participant) = Participant::find_by_id(&pool, q.from.id.0 as i64).await else {
+        bot.answer_callback_query(q.id)
+            .text("Ich kenne dich noch nicht. Schreibe mir privat und gib deine Daten mit /enter_data ein.")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+    if !participant.missing_fields().is_empty() {
+        bot.answer_callback_query(q.id)
+            .text("Deine Daten sind unvollständig. Schreibe mir privat, um sie zu ergänzen.")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    }
+    if participant
+        .signup(&pool, course_id)
+        .await?
+        .is_some_and(|signup| matches!(signup.status, Status::SignedUp))
+    {
+        bot.answer_callback_query(q.id)
+            .text("Du bist bereits angemeldet.")
+            .await?;
+        return Ok(());
+    }
+    bot.answer_callback_query(q.id)
+        .text("Ok, ich melde dich an. Das Ergebnis schicke ich dir privat.")
+        .await?;
+    participant
+        .set_signup_status(&pool, course_id, signup::Status::Notified)
+        .await?;
+    if sign_up(&bot, &pool, &http, &config, &participant, course_id).await? {
+        announcement
+            .add_signup(&pool, participant.id, &q.from.first_name)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn ignore_group_message(msg: Message) -> Result<()> {
+    log::debug!("ignoring message in group {}", msg.chat.id);
+    Ok(())
+}
@@ -1,15 +1,19 @@
 use crate::{
     bot::{
-        dialogue_utils, keyboards,
+        announcements, dialogue_utils, keyboards,
         message_effect::MessageEffect,
         schema::{MyDialogue, State},
         text_messages::TextMessage,
     },
-    models::{gender::Gender, participant::Participant, signup, status::Status},
+    config::AppConfig,
+    models::{
+        course::Course, gender::Gender, job::Job, participant::Participant, signup, status::Status,
+    },
+    utils::{http::HttpClient, validation},
 };
 use color_eyre::{eyre::eyre, Result};
 use sqlx::{Pool, Postgres};
-use strum::{EnumProperty, IntoEnumIterator};
+use std::sync::Arc;
 use teloxide::{prelude::*, types::KeyboardRemove};
 
 pub async fn receive_given_name(
@@ -17,21 +21,30 @@ pub async fn receive_given_name(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_given_name by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.given_name = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::text) {
+        Some(Ok(given_name)) => {
+            participant.given_name = Some(given_name);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceiveLastName(true), bot, dialogue, &pool).await?;
+                dialogue_utils::update(State::ReceiveLastName(true), bot, dialogue, &pool, &config)
+                    .await?;
             } else {
                 bot.send_message(msg.chat.id, "Vorname geändert.").await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib deinen Vornamen erneut ein."),
+            )
+            .await?;
+        }
         None => {
             bot.send_message(
                 msg.chat.id,
@@ -48,21 +61,30 @@ pub async fn receive_last_name(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_last_name by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.last_name = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::text) {
+        Some(Ok(last_name)) => {
+            participant.last_name = Some(last_name);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceiveGender(true), bot, dialogue, &pool).await?;
+                dialogue_utils::update(State::ReceiveGender(true), bot, dialogue, &pool, &config)
+                    .await?;
             } else {
                 bot.send_message(msg.chat.id, "Nachname geändert.").await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib deinen Nachnamen erneut ein."),
+            )
+            .await?;
+        }
         None => {
             bot.send_message(
                 msg.chat.id,
@@ -74,40 +96,35 @@ pub async fn receive_last_name(
     Ok(())
 }
 
-pub async fn receive_gender(
+pub async fn receive_gender(bot: Bot, dialogue: MyDialogue, msg: Message) -> Result<()> {
+    log::info!("receive_gender by chat {}", msg.chat.id);
+    bot.send_message(
+        dialogue.chat_id(),
+        "Bitte wähle dein Geschlecht über die Buttons aus.",
+    )
+    .reply_markup(keyboards::gender())
+    .await?;
+    Ok(())
+}
+
+/// Stores the chosen gender of the selected profile and moves on in the dialogue.
+pub async fn set_gender(
     bot: Bot,
     dialogue: MyDialogue,
-    msg: Message,
-    pool: Pool<Postgres>,
+    pool: &Pool<Postgres>,
+    config: &AppConfig,
+    gender: Gender,
 ) -> Result<()> {
-    log::info!("receive_gender by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, dialogue.chat_id().0).await?;
-    if let Some(Some(gender)) = msg.text().map(|text| {
-        Gender::iter().find(|g| {
-            g.get_str("pretty")
-                .unwrap_or_else(|| panic!("Better set that enum prop"))
-                == text
-        })
-    }) {
-        participant.gender = Some(gender);
-        participant.update(&pool).await?;
-        let state = dialogue_utils::state(&dialogue).await?;
-        if state.is_in_dialogue() {
-            dialogue_utils::update(State::ReceiveStreet(true), bot, dialogue, &pool).await?;
-        } else {
-            bot.send_message(dialogue.chat_id(), "Geschlecht geändert.")
-                .reply_markup(KeyboardRemove::default())
-                .await?;
-            dialogue.reset().await.map_err(|e| eyre!(e))?;
-        }
+    let mut participant = Participant::selected(pool, dialogue.chat_id().0).await?;
+    participant.gender = Some(gender);
+    participant.update(pool).await?;
+    let state = dialogue_utils::state(&dialogue).await?;
+    if state.is_in_dialogue() {
+        dialogue_utils::update(State::ReceiveStreet(true), bot, dialogue, pool, config).await?;
     } else {
-        let keyboard = keyboards::gender();
-        bot.send_message(
-            dialogue.chat_id(),
-            "Das habe ich nicht verstanden. Bitte wähle dein Geschlecht aus.",
-        )
-        .reply_markup(keyboard)
-        .await?;
+        bot.send_message(dialogue.chat_id(), "Geschlecht geändert.")
+            .await?;
+        dialogue_utils::reset(&bot, &dialogue, pool, config).await?;
     }
     Ok(())
 }
@@ -117,22 +134,31 @@ pub async fn receive_street(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_street by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.street = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::text) {
+        Some(Ok(street)) => {
+            participant.street = Some(street);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceiveCity(true), bot, dialogue, &pool).await?;
+                dialogue_utils::update(State::ReceiveCity(true), bot, dialogue, &pool, &config)
+                    .await?;
             } else {
                 bot.send_message(msg.chat.id, "Straße und Hausnummer geändert.")
                     .await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib deine Straße und deine Hausnummer erneut ein."),
+            )
+            .await?;
+        }
         None => {
             bot.send_message(msg.chat.id, "Das habe ich nicht verstanden. Bitte gib deine Straße und deine Hausnummer ein. Beispiel: Musterstr. 123")
                 .await?;
@@ -146,22 +172,27 @@ pub async fn receive_city(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_city by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.city = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::city) {
+        Some(Ok(city)) => {
+            participant.city = Some(city);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceivePhone(true), bot, dialogue, &pool).await?;
+                dialogue_utils::update(State::ReceivePhone(true), bot, dialogue, &pool, &config)
+                    .await?;
             } else {
                 bot.send_message(msg.chat.id, "Postleitzahl und Ort geändert.")
                     .await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(msg.chat.id, err.to_string()).await?;
+        }
         None => {
             bot.send_message(msg.chat.id, "Das habe ich nicht verstanden. Bitte gib deine Postleitzahl und deinen Ort ein.\n\nBeispiel: 50678 Köln")
                 .await?;
@@ -175,23 +206,37 @@ pub async fn receive_phone(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_phone by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.phone = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::phone) {
+        Some(Ok(phone)) => {
+            participant.phone = Some(phone);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceiveEmail(true, None), bot, dialogue, &pool)
-                    .await?;
+                dialogue_utils::update(
+                    State::ReceiveEmail(true, None),
+                    bot,
+                    dialogue,
+                    &pool,
+                    &config,
+                )
+                .await?;
             } else {
                 bot.send_message(msg.chat.id, "Telefonnummer geändert.")
                     .await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib deine Telefonnummer erneut ein."),
+            )
+            .await?;
+        }
         None => {
             bot.send_message(
                 msg.chat.id,
@@ -208,12 +253,13 @@ pub async fn receive_email(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_email by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.email = Some(text.to_string());
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
+    match msg.text().map(validation::email) {
+        Some(Ok(email)) => {
+            participant.email = Some(email);
             participant.update(&pool).await?;
             let state = dialogue_utils::state(&dialogue).await?;
             let message_id = match state {
@@ -224,13 +270,19 @@ pub async fn receive_email(
             bot.edit_message_reply_markup(dialogue.chat_id(), message_id)
                 .await?;
             if state.is_in_dialogue() {
-                dialogue_utils::update(State::ReceiveStatus(true), bot, dialogue, &pool).await?;
+                dialogue_utils::update(State::ReceiveStatus(true), bot, dialogue, &pool, &config)
+                    .await?;
             } else {
                 bot.send_message(msg.chat.id, "E-Mail-Adresse geändert.")
                     .await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
+        Some(Err(err)) => {
+            bot.send_message(msg.chat.id, err.to_string())
+                .reply_markup(keyboards::no_answer())
+                .await?;
+        }
         None => {
             bot.send_message(
                 msg.chat.id,
@@ -243,53 +295,54 @@ pub async fn receive_email(
     Ok(())
 }
 
-pub async fn receive_status(
+pub async fn receive_status(bot: Bot, dialogue: MyDialogue, msg: Message) -> Result<()> {
+    log::info!("receive_status by chat {}", msg.chat.id);
+    bot.send_message(
+        dialogue.chat_id(),
+        "Bitte wähle deinen Status über die Buttons aus.",
+    )
+    .reply_markup(keyboards::status())
+    .await?;
+    Ok(())
+}
+
+/// Stores the chosen status of the selected profile and asks for the status info if needed.
+pub async fn set_status(
     bot: Bot,
     dialogue: MyDialogue,
-    msg: Message,
-    pool: Pool<Postgres>,
+    pool: &Pool<Postgres>,
+    config: &AppConfig,
+    status: Status,
 ) -> Result<()> {
-    log::info!("receive_status by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, dialogue.chat_id().0).await?;
-    if let Some(Some(status)) = msg.text().map(|text| {
-        Status::iter().find(|s| {
-            s.get_str("pretty")
-                .unwrap_or_else(|| panic!("Better set that enum prop"))
-                == text
-        })
-    }) {
-        participant.status = Some(status.clone());
-        if Some(status.clone()) == Some(Status::Gast) {
-            participant.status_info = None;
-        }
-        participant.update(&pool).await?;
-        let state = dialogue_utils::state(&dialogue).await?;
-        if state.is_in_dialogue() {
-            if Some(status) == Some(Status::Gast) {
-                bot.send_message(msg.chat.id, TextMessage::EnterDataComplete.to_string())
-                    .message_effect_id(MessageEffect::Celebration.id())
-                    .await?;
-            } else {
-                dialogue_utils::update(State::ReceiveStatusInfo(true), bot, dialogue, &pool)
-                    .await?;
-            }
+    let mut participant = Participant::selected(pool, dialogue.chat_id().0).await?;
+    participant.status = Some(status.clone());
+    if status == Status::Gast {
+        participant.status_info = None;
+    }
+    participant.update(pool).await?;
+    let state = dialogue_utils::state(&dialogue).await?;
+    if state.is_in_dialogue() {
+        if status == Status::Gast {
+            bot.send_message(
+                dialogue.chat_id(),
+                TextMessage::EnterDataComplete.to_string(),
+            )
+            .message_effect_id(MessageEffect::Celebration.id())
+            .await?;
+            dialogue_utils::reset(&bot, &dialogue, pool, config).await?;
         } else {
-            bot.send_message(dialogue.chat_id(), "Status geändert.")
-                .reply_markup(KeyboardRemove::default())
+            dialogue_utils::update(State::ReceiveStatusInfo(true), bot, dialogue, pool, config)
                 .await?;
-            if Some(status) != Some(Status::Gast) {
-                dialogue_utils::update(State::ReceiveStatusInfo(false), bot, dialogue, &pool)
-                    .await?;
-            }
         }
     } else {
-        let keyboard = keyboards::status();
-        bot.send_message(
-            dialogue.chat_id(),
-            "Das habe ich nicht verstanden. Bitte wähle deinen Status aus:",
-        )
-        .reply_markup(keyboard)
-        .await?;
+        bot.send_message(dialogue.chat_id(), "Status geändert.")
+            .await?;
+        if status == Status::Gast {
+            dialogue_utils::reset(&bot, &dialogue, pool, config).await?;
+        } else {
+            dialogue_utils::update(State::ReceiveStatusInfo(false), bot, dialogue, pool, config)
+                .await?;
+        }
     }
     Ok(())
 }
@@ -299,14 +352,27 @@ pub async fn receive_status_info(
     msg: Message,
     dialogue: MyDialogue,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_status_info by chat {}", msg.chat.id);
-    let mut participant = Participant::find_by_id(&pool, msg.chat.id.0).await?;
+    let mut participant = Participant::selected(&pool, msg.chat.id.0).await?;
     let status_info_name = participant.status_info_name().unwrap_or_default();
     let state = dialogue_utils::state(&dialogue).await?;
-    match msg.text() {
-        Some(text) => {
-            participant.status_info = Some(text.to_string());
+    let Some(status) = participant.status.clone() else {
+        bot.send_message(
+            msg.chat.id,
+            "Du musst zuerst deinen Status auswählen: /edit_status",
+        )
+        .await?;
+        dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+        return Ok(());
+    };
+    match msg
+        .text()
+        .map(|text| validation::status_info(&status, text))
+    {
+        Some(Ok(status_info)) => {
+            participant.status_info = Some(status_info);
             participant.update(&pool).await?;
             if state.is_in_dialogue() {
                 bot.send_message(msg.chat.id, TextMessage::EnterDataComplete.to_string())
@@ -316,7 +382,14 @@ pub async fn receive_status_info(
                 bot.send_message(msg.chat.id, format!("{status_info_name} geändert.",))
                     .await?;
             }
-            dialogue.reset().await.map_err(|e| eyre!(e))?;
+            dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
+        }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib deine {status_info_name} erneut ein."),
+            )
+            .await?;
         }
         None => {
             bot.send_message(
@@ -333,75 +406,277 @@ pub async fn receive_signup_response(
     bot: Bot,
     dialogue: MyDialogue,
     msg: Message,
+    course_id: i64,
     pool: Pool<Postgres>,
+    http: HttpClient,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     log::info!("receive_signup_response by chat {}", msg.chat.id);
-    let participant = Participant::find_by_id(&pool, dialogue.chat_id().0).await?;
-    if let Some(Some(signup_request)) = msg.text().map(|text| {
-        signup::Request::iter()
-            .find(|s| {
-                s.get_str("pretty")
-                    .unwrap_or_else(|| panic!("Better set that enum prop"))
-                    == text
-            })
-            .or_else(|| {
-                let clean = text.trim().to_lowercase();
-                if clean.contains("ja") || clean.contains("yes") || clean.contains("schwanz") {
-                    Some(signup::Request::Accept)
-                } else {
-                    Some(signup::Request::Reject)
+    let text = msg
+        .text()
+        .or_else(|| msg.caption())
+        .or_else(|| msg.sticker().and_then(|sticker| sticker.emoji.as_deref()))
+        .unwrap_or_default();
+    let course = Course::find_by_id(&pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    if course.has_started() {
+        // No matter the answer, the participant gets told that the course is over.
+        return answer_signup(bot, dialogue, &pool, &http, &config, course_id, None).await;
+    }
+    let profiles = Participant::pending_profiles(&pool, dialogue.chat_id().0, course_id).await?;
+    if let Some(selection) = signup::Selection::parse(text, &profiles) {
+        return answer_signup(
+            bot,
+            dialogue,
+            &pool,
+            &http,
+            &config,
+            course_id,
+            Some(selection),
+        )
+        .await;
+    }
+    match signup::Intent::parse(text) {
+        signup::Intent::Yes => {
+            answer_signup(
+                bot,
+                dialogue,
+                &pool,
+                &http,
+                &config,
+                course_id,
+                Some(signup::Selection::Me),
+            )
+            .await
+        }
+        signup::Intent::No => {
+            answer_signup(bot, dialogue, &pool, &http, &config, course_id, None).await
+        }
+        signup::Intent::Maybe => {
+            bot.send_message(
+                dialogue.chat_id(),
+                "Kein Problem, überleg es dir in Ruhe. Sag mir einfach Bescheid, sobald du dich entschieden hast, oder lass dich später erinnern.",
+            )
+            .reply_markup(keyboards::signup(course_id, &profiles))
+            .await?;
+            Ok(())
+        }
+        signup::Intent::Unclear => {
+            bot.send_message(
+                dialogue.chat_id(),
+                "Das habe ich nicht verstanden. Möchtest du dich anmelden? Antworte mit \"Ja\" oder \"Nein\" oder nutze die Buttons.",
+            )
+            .reply_markup(keyboards::signup(course_id, &profiles))
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+/// Signs up the profiles given by `selection` for the course, or rejects the course for all
+/// pending profiles of the chat if there is none.
+pub async fn answer_signup(
+    bot: Bot,
+    dialogue: MyDialogue,
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    config: &AppConfig,
+    course_id: i64,
+    selection: Option<signup::Selection>,
+) -> Result<()> {
+    let participant = Participant::find_by_id(pool, dialogue.chat_id().0).await?;
+    let profiles = Participant::pending_profiles(pool, participant.chat_id, course_id).await?;
+    Job::Remind {
+        participant_id: participant.id,
+        course_id,
+    }
+    .cancel(pool)
+    .await?;
+    let course = Course::find_by_id(pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    if course.has_started() {
+        bot.send_message(
+            dialogue.chat_id(),
+            "Dieser Kurs hat bereits begonnen, eine Anmeldung ist nicht mehr möglich. Ich melde mich beim nächsten Kurs wieder!",
+        )
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+        for target in std::iter::once(&participant).chain(profiles.iter()) {
+            target
+                .set_signup_status(pool, course_id, signup::Status::Expired)
+                .await?;
+        }
+        dialogue_utils::reset(&bot, &dialogue, pool, config).await?;
+        return Ok(());
+    }
+    match selection {
+        Some(selection) => {
+            let targets: Vec<&Participant> = match selection {
+                signup::Selection::Me => vec![&participant],
+                signup::Selection::Profile(id) => {
+                    profiles.iter().filter(|profile| profile.id == id).collect()
                 }
-            })
-    }) {
-        let course_id = match dialogue_utils::state(&dialogue).await? {
-            State::ReceiveSignupResponse(course_id) => Some(course_id),
-            _ => None,
-        }
-        .ok_or_else(|| eyre!("not in state ReceiveSignupResponse"))?;
-        match signup_request {
-            signup::Request::Accept => {
-                bot.send_message(msg.chat.id, "Ok, einen Moment bitte...")
-                    .message_effect_id(MessageEffect::Heart.id())
-                    .reply_markup(KeyboardRemove::default())
-                    .await?;
-                match signup::perform(&participant, course_id).await {
-                    Ok(_) => {
-                        participant
-                            .set_signup_status(&pool, course_id, signup::Status::SignedUp)
-                            .await?;
-                        bot.send_message(
-                            msg.chat.id,
-                            "Das hat geklappt! Wenn du deine E-Mail-Adresse angegeben hast, findest du gleich eine Bestätigung in deinem Postfach."
-                        )
-                            .message_effect_id(MessageEffect::ThumbsUp.id())
-                        .await?;
-                    }
-                    Err(err) => {
-                        bot.send_message(
-                            msg.chat.id,
-                            format!("Fehler bei der Anmeldung:\n\n{err}\n\nWenn du das Problem selber beheben kannst, versuche es später noch einmal mit /signup. Melde dich ansonsten bei den Entwicklern."),
-                        )
-                            .message_effect_id(MessageEffect::Poop.id())
-                        .await?;
-                    }
-                };
+                signup::Selection::All => std::iter::once(&participant)
+                    .chain(profiles.iter())
+                    .collect(),
+            };
+            bot.send_message(dialogue.chat_id(), "Ok, einen Moment bitte...")
+                .message_effect_id(MessageEffect::Heart.id())
+                .reply_markup(KeyboardRemove::default())
+                .await?;
+            for target in targets {
+                sign_up(&bot, pool, http, config, target, course_id).await?;
             }
-            signup::Request::Reject => {
-                bot.send_message(msg.chat.id, "Ok, dann vielleicht beim nächsten Mal! Solltest du dich umentscheiden, kannst du den /signup Befehl nutzen, um dich doch noch anzumelden.")
-                    .message_effect_id(MessageEffect::ThumbsDown.id())
-                    .reply_markup(KeyboardRemove::default())
-                    .await?;
-                participant
-                    .set_signup_status(&pool, course_id, signup::Status::Rejected)
+        }
+        None => {
+            bot.send_message(dialogue.chat_id(), "Ok, dann vielleicht beim nächsten Mal! Solltest du dich umentscheiden, kannst du den /signup Befehl nutzen, um dich doch noch anzumelden.")
+                .message_effect_id(MessageEffect::ThumbsDown.id())
+                .reply_markup(KeyboardRemove::default())
+                .await?;
+            for target in std::iter::once(&participant).chain(profiles.iter()) {
+                target
+                    .set_signup_status(pool, course_id, signup::Status::Rejected)
                     .await?;
             }
         }
-        dialogue.reset().await.map_err(|e| eyre!(e))?;
+    }
+    dialogue_utils::reset(&bot, &dialogue, pool, config).await?;
+    Ok(())
+}
+
+/// Signs up the participant and informs their chat about the result. Returns whether the signup
+/// was successful.
+pub async fn sign_up(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    config: &AppConfig,
+    participant: &Participant,
+    course_id: i64,
+) -> Result<bool> {
+    let prefix = participant
+        .profile_name
+        .as_ref()
+        .map_or_else(String::new, |profile_name| {
+            format!("Profil {profile_name}: ")
+        });
+    match signup::attempt(pool, http, participant, course_id).await {
+        Ok(_) => {
+            participant
+                .set_signup_status(pool, course_id, signup::Status::SignedUp)
+                .await?;
+            bot.send_message(
+                ChatId(participant.chat_id),
+                format!("{prefix}Das hat geklappt! Wenn du eine E-Mail-Adresse angegeben hast, findest du gleich eine Bestätigung im Postfach."),
+            )
+            .message_effect_id(MessageEffect::ThumbsUp.id())
+            .reply_markup(keyboards::roster(course_id))
+            .await?;
+            announcements::refresh(bot, pool, course_id, config.timezone.0).await?;
+            Ok(true)
+        }
+        Err(err) => {
+            bot.send_message(
+                ChatId(participant.chat_id),
+                format!("{prefix}Fehler bei der Anmeldung:\n\n{err}\n\nWenn du das Problem selber beheben kannst, versuche es später noch einmal mit /signup. Melde dich ansonsten bei den Entwicklern."),
+            )
+            .message_effect_id(MessageEffect::Poop.id())
+            .await?;
+            Ok(false)
+        }
+    }
+}
+
+pub async fn receive_profile_name(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_profile_name by chat {}", msg.chat.id);
+    match msg.text().map(validation::text) {
+        Some(Ok(profile_name)) => {
+            let profiles = Participant::profiles(&pool, msg.chat.id.0).await?;
+            let reserved = ["Mich", "Beide", "Alle"];
+            if reserved
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&profile_name))
+                || profiles.iter().any(|profile| {
+                    profile
+                        .profile_name
+                        .as_ref()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(&profile_name))
+                })
+            {
+                bot.send_message(
+                    msg.chat.id,
+                    "Diesen Namen kannst du nicht verwenden. Bitte gib einen anderen Namen ein.",
+                )
+                .await?;
+                return Ok(());
+            }
+            let profile =
+                Participant::create_profile(&pool, msg.chat.id.0, profile_name.clone()).await?;
+            profile.select(&pool).await?;
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "Profil {profile_name} angelegt. Gib jetzt die Daten für dieses Profil ein."
+                ),
+            )
+            .await?;
+            dialogue_utils::update(State::ReceiveGivenName(true), bot, dialogue, &pool, &config)
+                .await?;
+        }
+        Some(Err(err)) => {
+            bot.send_message(
+                msg.chat.id,
+                format!("{err}\n\nBitte gib den Namen des Profils erneut ein."),
+            )
+            .await?;
+        }
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "Das habe ich nicht verstanden. Bitte gib den Namen des Profils ein.",
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn receive_profile_selection(
+    bot: Bot,
+    dialogue: MyDialogue,
+    msg: Message,
+    pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
+) -> Result<()> {
+    log::info!("receive_profile_selection by chat {}", msg.chat.id);
+    let profiles = Participant::profiles(&pool, msg.chat.id.0).await?;
+    if let Some(profile) = msg
+        .text()
+        .and_then(|text| profiles.iter().find(|p| p.profile_label() == text))
+    {
+        profile.select(&pool).await?;
+        bot.send_message(
+            msg.chat.id,
+            TextMessage::ShowData(profile.clone()).to_string(),
+        )
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .reply_markup(KeyboardRemove::default())
+        .await?;
+        dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
     } else {
         bot.send_message(
-            dialogue.chat_id(),
-            "Das habe ich nicht verstanden. Versuche es mit /help.",
+            msg.chat.id,
+            "Das habe ich nicht verstanden. Bitte wähle ein Profil aus.",
         )
+        .reply_markup(keyboards::profiles(&profiles))
         .await?;
     }
     Ok(())
@@ -412,14 +687,23 @@ pub async fn receive_delete_confirmation(
     dialogue: MyDialogue,
     msg: Message,
     pool: Pool<Postgres>,
+    config: Arc<AppConfig>,
 ) -> Result<()> {
     match msg.text() {
         Some(text) => {
             if text == "JA" {
-                let mut participant = Participant::find_by_id(&pool, dialogue.chat_id().0).await?;
+                let mut participant = Participant::selected(&pool, dialogue.chat_id().0).await?;
                 participant.delete(&pool).await?;
-                bot.send_message(dialogue.chat_id(), "Daten gelöscht.\n\nWenn du dich wieder anmelden möchtest, nutze den /enter_data Befehl.").await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                if participant.is_main_profile() {
+                    bot.send_message(dialogue.chat_id(), "Daten gelöscht.\n\nWenn du dich wieder anmelden möchtest, nutze den /enter_data Befehl.").await?;
+                } else {
+                    bot.send_message(
+                        dialogue.chat_id(),
+                        format!("Profil {} gelöscht.", participant.profile_label()),
+                    )
+                    .await?;
+                }
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             } else {
                 bot.send_message(
                     dialogue.chat_id(),
@@ -427,7 +711,7 @@ pub async fn receive_delete_confirmation(
                 )
                 .parse_mode(teloxide::types::ParseMode::Html)
                 .await?;
-                dialogue.reset().await.map_err(|e| eyre!(e))?;
+                dialogue_utils::reset(&bot, &dialogue, &pool, &config).await?;
             }
         }
         None => {
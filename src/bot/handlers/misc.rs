@@ -1,6 +1,17 @@
+use crate::models::participant::Participant;
 use color_eyre::Result;
+use sqlx::{Pool, Postgres};
 use teloxide::prelude::*;
 
+pub async fn touch_participant(update: Update, pool: Pool<Postgres>) {
+    let Some(chat) = update.chat() else {
+        return;
+    };
+    if let Err(err) = Participant::touch(&pool, chat.id.0).await {
+        log::error!("failed to record interaction of chat {}: {}", chat.id, err);
+    }
+}
+
 pub async fn invalid(bot: Bot, msg: Message) -> Result<()> {
     log::info!("invalid by chat {}", msg.chat.id);
     bot.send_message(
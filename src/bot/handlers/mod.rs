@@ -1,9 +1,11 @@
 pub mod callback_queries;
 pub mod commands;
+pub mod groups;
 pub mod messages;
 pub mod misc;
 
 pub use callback_queries::*;
 pub use commands::*;
+pub use groups::*;
 pub use messages::*;
 pub use misc::*;
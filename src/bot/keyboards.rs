@@ -1,60 +1,145 @@
-use crate::models::{gender::Gender, signup, status::Status};
+use crate::{
+    bot::callback_data::CallbackData,
+    models::{gender::Gender, participant::Participant, reminder::Delay, signup, status::Status},
+};
 use strum::{EnumProperty, IntoEnumIterator};
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup};
 
-pub fn gender() -> KeyboardMarkup {
-    let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
+pub fn gender() -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
     for gender in Gender::iter() {
-        let row = vec![KeyboardButton::new(
+        let row = vec![InlineKeyboardButton::callback(
             gender
                 .get_str("pretty")
                 .unwrap_or_else(|| panic!("Better set that enum prop")),
+            CallbackData::Gender(gender.clone()).to_string(),
         )];
         keyboard.push(row);
     }
 
-    KeyboardMarkup::new(keyboard)
-        .resize_keyboard()
-        .one_time_keyboard()
+    InlineKeyboardMarkup::new(keyboard)
 }
 
-pub fn status() -> KeyboardMarkup {
-    let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
+pub fn status() -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
     for status in Status::iter() {
-        let row = vec![KeyboardButton::new(
+        let row = vec![InlineKeyboardButton::callback(
             status
                 .get_str("pretty")
                 .unwrap_or_else(|| panic!("Better set that enum prop")),
+            CallbackData::Status(status.clone()).to_string(),
         )];
         keyboard.push(row);
     }
 
-    KeyboardMarkup::new(keyboard).one_time_keyboard()
+    InlineKeyboardMarkup::new(keyboard)
 }
 
-pub fn signup() -> KeyboardMarkup {
-    let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
+/// Offers the additional `profiles` of the chat next to the regular answers, if there are any.
+pub fn signup(course_id: i64, profiles: &[Participant]) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
-    for signup_request in signup::Request::iter() {
-        let row = vec![KeyboardButton::new(
-            signup_request
+    if profiles.is_empty() {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            signup::Request::Accept
                 .get_str("pretty")
                 .unwrap_or_else(|| panic!("Better set that enum prop")),
+            CallbackData::SignupAccept(course_id, signup::Selection::Me).to_string(),
+        )]);
+    } else {
+        for selection in signup::Selection::options(profiles) {
+            keyboard.push(vec![InlineKeyboardButton::callback(
+                selection.label(profiles),
+                CallbackData::SignupAccept(course_id, selection).to_string(),
+            )]);
+        }
+    }
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        signup::Request::Reject
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop")),
+        CallbackData::SignupReject(course_id).to_string(),
+    )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        signup::Request::Remind
+            .get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop")),
+        CallbackData::SignupRemind(course_id).to_string(),
+    )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "Wer kommt?",
+        CallbackData::Roster(course_id).to_string(),
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn remind(course_id: i64, delays: &[Delay]) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    for delay in delays {
+        let row = vec![InlineKeyboardButton::callback(
+            delay
+                .get_str("pretty")
+                .unwrap_or_else(|| panic!("Better set that enum prop")),
+            CallbackData::SignupSnooze(course_id, *delay).to_string(),
         )];
         keyboard.push(row);
     }
 
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn profiles(profiles: &[Participant]) -> KeyboardMarkup {
+    let mut keyboard: Vec<Vec<KeyboardButton>> = vec![];
+
+    for profile in profiles {
+        let row = vec![KeyboardButton::new(profile.profile_label())];
+        keyboard.push(row);
+    }
+
     KeyboardMarkup::new(keyboard)
         .resize_keyboard()
         .one_time_keyboard()
 }
 
+pub fn group_signup(course_id: i64) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    let row = vec![
+        InlineKeyboardButton::callback(
+            "Anmelden",
+            CallbackData::GroupSignup(course_id).to_string(),
+        ),
+        InlineKeyboardButton::callback("Wer kommt?", CallbackData::Roster(course_id).to_string()),
+    ];
+    keyboard.push(row);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn roster(course_id: i64) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    let row = vec![InlineKeyboardButton::callback(
+        "Wer kommt?",
+        CallbackData::Roster(course_id).to_string(),
+    )];
+    keyboard.push(row);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
 pub fn no_answer() -> InlineKeyboardMarkup {
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
-    let row = vec![InlineKeyboardButton::callback("Keine Angabe.", "no answer")];
+    let row = vec![InlineKeyboardButton::callback(
+        "Keine Angabe.",
+        CallbackData::NoAnswer.to_string(),
+    )];
     keyboard.push(row);
 
     InlineKeyboardMarkup::new(keyboard)
@@ -1,7 +1,13 @@
+pub mod announcements;
+pub mod callback_data;
 pub mod dialogue_utils;
 pub mod handlers;
 pub mod keyboards;
 pub mod message_effect;
+pub mod reminders;
+pub mod scheduler;
 pub mod schema;
+pub mod signup_round;
 pub mod text_messages;
+pub mod worker;
 pub use schema::start;
@@ -0,0 +1,54 @@
+use crate::{
+    bot::{
+        dialogue_utils,
+        schema::{MyDialogue, MyStorage, State},
+    },
+    config::AppConfig,
+    models::{participant::Participant, signup},
+};
+use color_eyre::{eyre::eyre, Result};
+use sqlx::{Pool, Postgres};
+use teloxide::prelude::*;
+
+/// Sends the signup prompt for the course again. Returns `false` if the participant is busy
+/// entering data, so that the reminder should be tried again later.
+pub async fn send(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    config: &AppConfig,
+    participant_id: i64,
+    course_id: i64,
+) -> Result<bool> {
+    let participant = Participant::find_by_id(pool, participant_id).await?;
+    let dialogue = MyDialogue::new(storage.clone(), ChatId(participant.chat_id));
+    let state = dialogue.get().await.map_err(|e| eyre!(e))?;
+    // Do not interrupt the participant while they are entering data.
+    if !matches!(
+        state,
+        None | Some(State::Default | State::ReceiveSignupResponse(_))
+    ) {
+        return Ok(false);
+    }
+    let pending = participant
+        .signup(pool, course_id)
+        .await?
+        .is_some_and(|signup| matches!(signup.status, signup::Status::Notified));
+    if !pending {
+        return Ok(true);
+    }
+    log::info!(
+        "reminding chat {} of course {}",
+        participant.chat_id,
+        course_id
+    );
+    dialogue_utils::update(
+        State::ReceiveSignupResponse(course_id),
+        bot.clone(),
+        dialogue,
+        pool,
+        config,
+    )
+    .await?;
+    Ok(true)
+}
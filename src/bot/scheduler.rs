@@ -0,0 +1,168 @@
+use crate::{
+    bot::{
+        dialogue_utils,
+        schema::{MyDialogue, MyStorage, State},
+        text_messages::TextMessage,
+    },
+    config::AppConfig,
+    models::{
+        course::{BookingState, Course},
+        participant::Participant,
+        signup::Signup,
+    },
+    utils::http::HttpClient,
+};
+use color_eyre::{eyre::eyre, Result};
+use sqlx::{Pool, Postgres};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use teloxide::{
+    prelude::*,
+    types::{MessageId, ParseMode},
+};
+use tokio::time::Instant;
+
+/// How often the booking page of a course with unanswered prompts is checked for its closing.
+const BOOKING_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Runs the periodic tasks of the bot, for as long as the bot runs.
+pub async fn run(
+    bot: Bot,
+    pool: Pool<Postgres>,
+    storage: MyStorage,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    let mut booking_checks = HashMap::new();
+    loop {
+        interval.tick().await;
+        if let Err(e) =
+            expire_signup_prompts(&bot, &pool, &storage, &http, &config, &mut booking_checks).await
+        {
+            log::error!("failed to expire signup prompts: {}", e);
+        }
+        if let Err(e) = deliver_queued_prompts(&bot, &pool, &storage, &config).await {
+            log::error!("failed to deliver queued signup prompts: {}", e);
+        }
+    }
+}
+
+/// Delivers queued signup prompts to participants whose dialogue returned to the default state
+/// without going through `dialogue_utils::reset`.
+async fn deliver_queued_prompts(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    config: &AppConfig,
+) -> Result<()> {
+    for signup in Signup::queued(pool, None).await? {
+        // Additional profiles share the dialogue of their chat.
+        let participant = Participant::find_by_id(pool, signup.participant_id).await?;
+        let dialogue = MyDialogue::new(storage.clone(), ChatId(participant.chat_id));
+        let state = dialogue.get().await.map_err(|e| eyre!(e))?;
+        if matches!(state, None | Some(State::Default)) {
+            dialogue_utils::deliver_queued(bot, &dialogue, pool, config).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Resets the dialogues of participants who did not answer the signup prompt before the course
+/// started or its booking closed and marks the prompt as closed. The booking of each course is
+/// checked at most every `BOOKING_CHECK_INTERVAL`, remembered in `booking_checks`.
+async fn expire_signup_prompts(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    http: &HttpClient,
+    config: &AppConfig,
+    booking_checks: &mut HashMap<i64, Instant>,
+) -> Result<()> {
+    let courses = Course::with_unanswered_signups(pool).await?;
+    booking_checks.retain(|course_id, _| courses.iter().any(|course| course.id == *course_id));
+    for course in courses {
+        let reason = if course.has_started() {
+            "der Kurs hat bereits begonnen"
+        } else {
+            if booking_checks
+                .get(&course.id)
+                .is_some_and(|checked_at| checked_at.elapsed() < BOOKING_CHECK_INTERVAL)
+            {
+                continue;
+            }
+            booking_checks.insert(course.id, Instant::now());
+            // Only an explicit marker on the page closes the prompts, unknown pages are ignored.
+            match course.booking_state(http).await {
+                Ok(BookingState::Closed) => "die Buchung beim UniSport ist beendet",
+                Ok(_) => continue,
+                Err(e) => {
+                    log::warn!("failed to check the booking of course {}: {}", course.id, e);
+                    continue;
+                }
+            }
+        };
+        for signup in Signup::unanswered(pool, course.id).await? {
+            // A failed signup is expired again in the next run, the others go on.
+            if let Err(e) =
+                expire_signup_prompt(bot, pool, storage, config, &course, &signup, reason).await
+            {
+                log::error!(
+                    "failed to expire signup of participant {} for course {}: {}",
+                    signup.participant_id,
+                    course.id,
+                    e
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn expire_signup_prompt(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    config: &AppConfig,
+    course: &Course,
+    signup: &Signup,
+    reason: &str,
+) -> Result<()> {
+    let participant = Participant::find_by_id(pool, signup.participant_id).await?;
+    // Additional profiles are offered in the prompt of the main profile.
+    if participant.is_main_profile() {
+        log::info!(
+            "expiring signup prompt of chat {} for course {}",
+            participant.chat_id,
+            course.id
+        );
+        let dialogue = MyDialogue::new(storage.clone(), ChatId(participant.chat_id));
+        let state = dialogue.get().await.map_err(|e| eyre!(e))?;
+        if matches!(state, Some(State::ReceiveSignupResponse(course_id)) if course_id == course.id)
+        {
+            dialogue.reset().await.map_err(|e| eyre!(e))?;
+        }
+        if let Some(message_id) = signup.prompt_message_id {
+            // Prompts that cannot be edited anymore, e.g. because they were deleted, are left as
+            // they are.
+            if let Err(e) = bot
+                .edit_message_text(
+                    ChatId(participant.chat_id),
+                    MessageId(message_id),
+                    format!(
+                        "{}\n\n<i>Die Anmeldung ist geschlossen, {reason}.</i>",
+                        TextMessage::SignupResponse(course.clone(), config.timezone.0)
+                    ),
+                )
+                .parse_mode(ParseMode::Html)
+                .await
+            {
+                log::error!(
+                    "failed to edit signup prompt of chat {}: {}",
+                    participant.chat_id,
+                    e
+                );
+            }
+        }
+    }
+    signup.expire(pool).await
+}
@@ -1,7 +1,11 @@
-use crate::bot::handlers;
+use crate::{
+    bot::{callback_data::CallbackData, handlers, scheduler, worker},
+    config::AppConfig,
+    utils::http::HttpClient,
+};
 use color_eyre::Result;
 use sqlx::{Pool, Postgres};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use teloxide::{
     dispatching::{
         dialogue::{self, serializer::Bincode, ErasedStorage, RedisStorage, Storage},
@@ -30,6 +34,8 @@ pub enum State {
     ReceiveStatusInfo(bool),
     ReceiveSignupResponse(i64),
     ReceiveDeleteConfirmation,
+    ReceiveProfileName,
+    ReceiveProfileSelection,
 }
 
 impl State {
@@ -44,9 +50,11 @@ impl State {
             | Self::ReceiveEmail(in_dialogue, _)
             | Self::ReceiveStatus(in_dialogue)
             | Self::ReceiveStatusInfo(in_dialogue) => in_dialogue,
-            Self::Default | Self::ReceiveSignupResponse(_) | Self::ReceiveDeleteConfirmation => {
-                &false
-            }
+            Self::Default
+            | Self::ReceiveSignupResponse(_)
+            | Self::ReceiveDeleteConfirmation
+            | Self::ReceiveProfileName
+            | Self::ReceiveProfileSelection => &false,
         }
     }
 }
@@ -89,18 +97,60 @@ pub enum Command {
     EditStatusInfo,
     #[command(description = "Starttext anzeigen")]
     Start,
+    #[command(description = "Daten beim UniSport prüfen, ohne dich anzumelden")]
+    CheckData,
+    #[command(description = "Alle gespeicherten Daten exportieren")]
+    Export,
+    #[command(description = "Profile anzeigen")]
+    Profiles,
+    #[command(description = "Profil für eine weitere Person anlegen")]
+    AddProfile,
+    #[command(description = "Zu bearbeitendes Profil wechseln")]
+    SwitchProfile,
+    #[command(description = "Anzeigen, wer heute zum Training kommt")]
+    Wer,
+    #[command(description = "Namentliche Anzeige bei /wer ein- oder ausschalten")]
+    EditShowInRoster,
 }
 
-pub async fn start(pool: Pool<Postgres>, redis_url: String) -> Result<()> {
+#[derive(BotCommands, Clone, Debug)]
+#[command(
+    description = "Diese Befehle sind in Gruppen verfügbar:",
+    rename_rule = "snake_case"
+)]
+pub enum GroupCommand {
+    #[command(description = "Kurse in dieser Gruppe ankündigen")]
+    RegisterGroup,
+    #[command(description = "Kurse in dieser Gruppe nicht mehr ankündigen")]
+    UnregisterGroup,
+}
+
+pub async fn start(pool: Pool<Postgres>, http: HttpClient, config: Arc<AppConfig>) -> Result<()> {
     let client = teloxide::net::default_reqwest_settings()
         .timeout(Duration::from_secs(60))
         .build()?;
-    let bot = Bot::from_env_with_client(client);
+    let bot = Bot::with_client(config.telegram_token()?, client);
     bot.set_my_commands(Command::bot_commands().into_iter().take(6))
         .await?;
-    let storage: MyStorage = RedisStorage::open(&redis_url, Bincode).await?.erase();
+    let storage: MyStorage = RedisStorage::open(config.redis_url()?, Bincode)
+        .await?
+        .erase();
+    tokio::spawn(scheduler::run(
+        bot.clone(),
+        pool.clone(),
+        storage.clone(),
+        http.clone(),
+        config.clone(),
+    ));
+    tokio::spawn(worker::run(
+        bot.clone(),
+        pool.clone(),
+        storage.clone(),
+        http.clone(),
+        config.clone(),
+    ));
     Dispatcher::builder(bot, schema())
-        .dependencies(dptree::deps![storage, pool])
+        .dependencies(dptree::deps![storage, pool, http, config])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
@@ -127,7 +177,14 @@ fn schema() -> UpdateHandler<color_eyre::Report> {
         .branch(case![Command::EditEmail].endpoint(handlers::edit_email))
         .branch(case![Command::EditStatus].endpoint(handlers::edit_status))
         .branch(case![Command::EditStatusInfo].endpoint(handlers::edit_status_info))
-        .branch(case![Command::Start].endpoint(handlers::start));
+        .branch(case![Command::Start].endpoint(handlers::start))
+        .branch(case![Command::CheckData].endpoint(handlers::check_data))
+        .branch(case![Command::Export].endpoint(handlers::export))
+        .branch(case![Command::Profiles].endpoint(handlers::profiles))
+        .branch(case![Command::AddProfile].endpoint(handlers::add_profile))
+        .branch(case![Command::SwitchProfile].endpoint(handlers::switch_profile))
+        .branch(case![Command::Wer].endpoint(handlers::wer))
+        .branch(case![Command::EditShowInRoster].endpoint(handlers::edit_show_in_roster));
 
     let message_handler = Update::filter_message()
         .branch(command_handler)
@@ -151,16 +208,69 @@ fn schema() -> UpdateHandler<color_eyre::Report> {
         .branch(
             case![State::ReceiveDeleteConfirmation].endpoint(handlers::receive_delete_confirmation),
         )
+        .branch(case![State::ReceiveProfileName].endpoint(handlers::receive_profile_name))
+        .branch(case![State::ReceiveProfileSelection].endpoint(handlers::receive_profile_selection))
         .branch(dptree::endpoint(handlers::invalid));
 
     let callback_query_handler = Update::filter_callback_query()
         .branch(
-            case![State::ReceiveEmail(in_dialogue, message_id)]
-                .endpoint(handlers::receive_email_callback),
+            dptree::filter_map(parse_callback_data)
+                .branch(
+                    case![CallbackData::Gender(gender)].endpoint(handlers::receive_gender_callback),
+                )
+                .branch(
+                    case![CallbackData::Status(status)].endpoint(handlers::receive_status_callback),
+                )
+                .branch(
+                    case![CallbackData::SignupAccept(course_id, selection)]
+                        .endpoint(handlers::receive_signup_accept_callback),
+                )
+                .branch(
+                    case![CallbackData::SignupReject(course_id)]
+                        .endpoint(handlers::receive_signup_reject_callback),
+                )
+                .branch(
+                    case![CallbackData::SignupRemind(course_id)]
+                        .endpoint(handlers::receive_signup_remind_callback),
+                )
+                .branch(
+                    case![CallbackData::SignupSnooze(course_id, delay)]
+                        .endpoint(handlers::receive_signup_snooze_callback),
+                )
+                .branch(case![CallbackData::NoAnswer].endpoint(handlers::receive_email_callback)),
         )
         .branch(dptree::endpoint(handlers::invalid_callback_query));
 
-    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
-        .branch(message_handler)
-        .branch(callback_query_handler)
+    // Group chats have no dialogue. They only receive announcements and signups from members.
+    let group_message_handler = Update::filter_message()
+        .filter(|msg: Message| !msg.chat.is_private())
+        .branch(
+            teloxide::filter_command::<GroupCommand, _>()
+                .branch(case![GroupCommand::RegisterGroup].endpoint(handlers::register_group))
+                .branch(case![GroupCommand::UnregisterGroup].endpoint(handlers::unregister_group)),
+        )
+        .branch(dptree::endpoint(handlers::ignore_group_message));
+
+    // Buttons in announcements and rosters work without a dialogue, also in group chats.
+    let stateless_callback_query_handler = Update::filter_callback_query()
+        .filter_map(parse_callback_data)
+        .branch(
+            case![CallbackData::GroupSignup(course_id)]
+                .endpoint(handlers::receive_group_signup_callback),
+        )
+        .branch(case![CallbackData::Roster(course_id)].endpoint(handlers::receive_roster_callback));
+
+    dptree::entry()
+        .branch(group_message_handler)
+        .branch(stateless_callback_query_handler)
+        .branch(
+            dialogue::enter::<Update, ErasedStorage<State>, State, _>()
+                .inspect_async(handlers::touch_participant)
+                .branch(message_handler)
+                .branch(callback_query_handler),
+        )
+}
+
+fn parse_callback_data(q: CallbackQuery) -> Option<CallbackData> {
+    q.data.as_deref().and_then(|data| data.parse().ok())
 }
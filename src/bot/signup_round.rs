@@ -0,0 +1,391 @@
+use crate::{
+    bot::{
+        announcements, keyboards,
+        message_effect::MessageEffect,
+        schema::{MyStorage, State},
+        text_messages::TextMessage,
+    },
+    config::AppConfig,
+    models::{
+        course::{BookingState, Course},
+        job::Job,
+        participant::Participant,
+        signup::{self, Session},
+    },
+    utils::http::HttpClient,
+};
+use chrono::{TimeDelta, Utc};
+use chrono_tz::Tz;
+use color_eyre::{eyre::eyre, Result};
+use sqlx::{Pool, Postgres};
+use std::{collections::HashMap, sync::Arc};
+use teloxide::prelude::*;
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{sleep, Duration},
+};
+
+/// How long before the announced opening the scheduled round starts polling the booking page.
+pub const LEAD_TIME: TimeDelta = TimeDelta::seconds(30);
+/// How long after the announced opening the booking page is polled before giving up for now.
+const POLL_TIMEOUT: TimeDelta = TimeDelta::minutes(2);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long after the announced opening the job is tried again before giving up.
+const MAX_WAIT: TimeDelta = TimeDelta::minutes(30);
+
+/// Waits for the booking of the course to open and runs the round. Returns `false` if the booking
+/// did not open in time, so that the job is tried again later. The job is given up once the
+/// booking is closed, the course started or the booking did not open for `MAX_WAIT`.
+pub async fn run_when_open(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    config: &AppConfig,
+    storage: &MyStorage,
+    course_id: i64,
+) -> Result<bool> {
+    let mut course = Course::find_by_id(pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    if course.has_started() {
+        log::info!(
+            "course {} has already started, not opening the signup",
+            course.id
+        );
+        return Ok(true);
+    }
+    let now = Utc::now().naive_utc();
+    let opens_at = course.signup_opens_at.unwrap_or(now);
+    if now > opens_at + MAX_WAIT {
+        log::info!(
+            "signup for course {} did not open since {opens_at}, giving up",
+            course.id
+        );
+        return Ok(true);
+    }
+    // Pages loaded after the announced opening are of no use if the booking is still closed.
+    let sessions = if config.signup.prewarm && now <= opens_at {
+        prewarm(pool, http, config, &course).await?
+    } else {
+        HashMap::new()
+    };
+    let deadline = opens_at.max(now) + POLL_TIMEOUT;
+    loop {
+        match course.booking_state(http).await {
+            Ok(BookingState::Open) => break,
+            Ok(BookingState::OpensAt(opens_at))
+                if opens_at - LEAD_TIME > Utc::now().naive_utc() + POLL_TIMEOUT =>
+            {
+                log::info!("signup for course {} was moved to {opens_at}", course.id);
+                course.set_signup_opens_at(pool, Some(opens_at)).await?;
+                Job::OpenSignup { course_id }
+                    .reschedule(pool, opens_at - LEAD_TIME)
+                    .await?;
+                return Ok(true);
+            }
+            Ok(BookingState::Closed) => {
+                log::info!("signup for course {} is closed, giving up", course.id);
+                return Ok(true);
+            }
+            Ok(state) => log::debug!("signup for course {} is not open yet: {state:?}", course.id),
+            Err(e) => log::warn!("failed to check the booking of course {}: {}", course.id, e),
+        }
+        if Utc::now().naive_utc() >= deadline {
+            log::info!("signup for course {} did not open in time", course.id);
+            // Without an announced opening, there is nothing to wait for.
+            return Ok(course.signup_opens_at.is_none());
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+    log::info!("signup for course {} is open", course.id);
+    run(bot, pool, http, config, storage, &course, sessions).await?;
+    Ok(true)
+}
+
+/// Prepares the signup sessions of all participants who want to be signed up always, so that
+/// their submissions can be sent right when the booking opens. Up to `signup.concurrency`
+/// sessions are prepared at the same time, so that many sessions fit into the lead time.
+async fn prewarm(
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    config: &AppConfig,
+    course: &Course,
+) -> Result<HashMap<i64, Session>> {
+    let semaphore = Arc::new(Semaphore::new(config.signup.concurrency.max(1)));
+    let mut prewarms = JoinSet::new();
+    for participant in Participant::auto_signups(pool, course.id, config.signup.order).await? {
+        if !participant.missing_fields().is_empty() {
+            continue;
+        }
+        let mut session = Session::new(http, &participant, course.id)?;
+        let permit = semaphore.clone().acquire_owned().await?;
+        prewarms.spawn(async move {
+            let _permit = permit;
+            // A session that could not be pre-warmed loads the signup page once the booking opens.
+            if let Err(e) = session.prewarm().await {
+                log::warn!(
+                    "failed to pre-warm the session of participant {}: {}",
+                    session.participant().id,
+                    e
+                );
+            }
+            session
+        });
+    }
+    let mut sessions = HashMap::new();
+    while let Some(session) = prewarms.join_next().await {
+        let session = session.map_err(|e| eyre!(e))?;
+        sessions.insert(session.participant().id, session);
+    }
+    log::info!(
+        "pre-warmed {} sessions for course {}",
+        sessions.len(),
+        course.id
+    );
+    Ok(sessions)
+}
+
+/// Announces the course, signs up the participants who want to be signed up always and asks
+/// everyone else whether they want to join. Participants that were handled before are skipped.
+/// Automatic signups use the pre-warmed `sessions` of their participants, if there are any.
+pub async fn run(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    config: &AppConfig,
+    storage: &MyStorage,
+    course: &Course,
+    mut sessions: HashMap<i64, Session>,
+) -> Result<()> {
+    log::info!("announcing course in groups");
+    announcements::send(bot, pool, course, config.timezone.0).await?;
+
+    let order = config.signup.order;
+    log::info!(
+        "signing up participants automatically ordered by {}",
+        order.as_ref()
+    );
+    let mut auto_signups = Vec::new();
+    for participant in Participant::auto_signups(pool, course.id, order).await? {
+        // Participants with incomplete data are asked to complete it below.
+        if !participant.missing_fields().is_empty() {
+            continue;
+        }
+        log::info!(
+            "participant {} wants to be signed up always",
+            participant.id
+        );
+        participant
+            .set_signup_status(pool, course.id, signup::Status::Notified)
+            .await?;
+        let session = match sessions.remove(&participant.id) {
+            Some(session) => session,
+            None => Session::new(http, &participant, course.id)?,
+        };
+        auto_signups.push(session);
+    }
+    // The signups are dispatched in their own task, so that informing the other participants
+    // does not wait for free permits.
+    let auto_signups = tokio::spawn(dispatch_auto_signups(
+        bot.clone(),
+        pool.clone(),
+        course.id,
+        config.timezone.0,
+        config.signup.concurrency,
+        auto_signups,
+    ));
+
+    log::info!("informing participants");
+    // Additional profiles come before the main profile of their chat, so that they are already
+    // pending when the main profile's signup prompt is sent.
+    for participant in &mut Participant::uninformed(pool, course.id).await? {
+        let missing_fields = participant.missing_fields();
+        if !missing_fields.is_empty() {
+            if !participant.is_main_profile() {
+                log::info!(
+                    "profile {} has incomplete data, skipping it",
+                    participant.id
+                );
+                continue;
+            }
+            log::info!(
+                "participant {} has incomplete data, asking them to complete it instead",
+                participant.id
+            );
+        } else if !participant.is_main_profile() {
+            // Without a complete main profile, the chat gets no signup prompt that could offer
+            // the profile, so it stays uninformed until a later round.
+            let main_profile = Participant::find_by_id(pool, participant.chat_id).await?;
+            if !main_profile.missing_fields().is_empty() {
+                log::info!(
+                    "main profile of profile {} has incomplete data, skipping it",
+                    participant.id
+                );
+                continue;
+            }
+            log::info!(
+                "profile {} will be offered in the signup prompt of chat {}",
+                participant.id,
+                participant.chat_id
+            );
+            participant
+                .set_signup_status(pool, course.id, signup::Status::Notified)
+                .await?;
+            continue;
+        }
+
+        // Participants that are currently editing their data get the prompt once they are done.
+        let state = storage
+            .clone()
+            .get_dialogue(ChatId(participant.chat_id))
+            .await
+            .map_err(|e| eyre!(e))?;
+        if !matches!(
+            state,
+            None | Some(State::ReceiveSignupResponse(_) | State::Default)
+        ) {
+            log::info!(
+                "participant {} is editing their data, queueing the signup prompt",
+                participant.id
+            );
+            participant
+                .set_signup_status(pool, course.id, signup::Status::Queued)
+                .await?;
+            continue;
+        }
+
+        log::info!("informing participant {}", participant.id);
+        let request = if missing_fields.is_empty() {
+            bot.send_message(
+                ChatId(participant.chat_id),
+                TextMessage::SignupResponse(course.clone(), config.timezone.0).to_string(),
+            )
+            .message_effect_id(MessageEffect::Fire.id())
+            .reply_markup(keyboards::signup(
+                course.id,
+                &Participant::pending_profiles(pool, participant.chat_id, course.id).await?,
+            ))
+        } else {
+            bot.send_message(
+                ChatId(participant.chat_id),
+                TextMessage::IncompleteData(missing_fields.clone()).to_string(),
+            )
+        };
+        match request.await {
+            Ok(msg) => {
+                participant
+                    .set_signup_status(pool, course.id, signup::Status::Notified)
+                    .await?;
+                if missing_fields.is_empty() {
+                    participant
+                        .set_signup_prompt(pool, course.id, msg.id.0)
+                        .await?;
+                    storage
+                        .clone()
+                        .update_dialogue(
+                            ChatId(participant.chat_id),
+                            State::ReceiveSignupResponse(course.id),
+                        )
+                        .await
+                        .map_err(|e| eyre!(e))?;
+                }
+                log::info!("successfully informed participant {}", participant.id)
+            }
+            Err(e) => {
+                log::error!("failed to inform participant {}: {}", participant.id, e);
+                if e.to_string().contains("bot was blocked by the user") {
+                    log::info!(
+                        "participant {} blocked the bot, deleting the participant and their dialogue now",
+                        participant.id
+                    );
+                    participant.delete(pool).await?;
+                    storage
+                        .clone()
+                        .remove_dialogue(ChatId(participant.chat_id))
+                        .await
+                        .map_err(|e| eyre!(e))?;
+                } else if e.to_string().contains("user is deactivated") {
+                    log::info!(
+                        "participant {} is a deactivated Telegram user, deleting the participant and their dialogue now",
+                        participant.id
+                    );
+                    participant.delete(pool).await?;
+                    storage
+                        .clone()
+                        .remove_dialogue(ChatId(participant.chat_id))
+                        .await
+                        .map_err(|e| eyre!(e))?;
+                }
+                continue;
+            }
+        };
+
+        let interval = config.telegram.message_interval_ms;
+        log::info!("sleep for {interval}ms to respect Telegram API rate limiting");
+        sleep(Duration::from_millis(interval)).await;
+    }
+
+    log::info!("waiting for the automatic signups");
+    auto_signups.await.map_err(|e| eyre!(e))?;
+
+    Ok(())
+}
+
+/// Signs up the participants of the sessions with at most `concurrency` signups at a time.
+/// Permits are acquired before a signup is spawned, so signups start in the given order. The
+/// announcements are refreshed here after every successful signup, so that edits do not race.
+async fn dispatch_auto_signups(
+    bot: Bot,
+    pool: Pool<Postgres>,
+    course_id: i64,
+    timezone: Tz,
+    concurrency: usize,
+    sessions: Vec<Session>,
+) {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut auto_signups = JoinSet::new();
+    for session in sessions {
+        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+            break;
+        };
+        let pool = pool.clone();
+        auto_signups.spawn(async move {
+            let _permit = permit;
+            auto_sign_up(&pool, session).await
+        });
+    }
+    while let Some(result) = auto_signups.join_next().await {
+        match result.map_err(|e| eyre!(e)).and_then(|r| r) {
+            Ok(true) => {
+                if let Err(err) = announcements::refresh(&bot, &pool, course_id, timezone).await {
+                    log::error!("failed to refresh announcements: {}", err);
+                }
+            }
+            Ok(false) => {}
+            Err(err) => log::error!("automatic signup failed: {}", err),
+        }
+    }
+}
+
+/// Returns whether the signup was successful.
+async fn auto_sign_up(pool: &Pool<Postgres>, session: Session) -> Result<bool> {
+    let (participant, course_id) = (session.participant().clone(), session.course_id());
+    match signup::attempt_session(pool, session).await {
+        Ok(()) => {
+            participant
+                .set_signup_status(pool, course_id, signup::Status::SignedUp)
+                .await?;
+            Ok(true)
+        }
+        Err(err) => {
+            log::error!(
+                "failed to sign up participant {} for {}: {}",
+                participant.id,
+                course_id,
+                err
+            );
+            Ok(false)
+        }
+    }
+}
@@ -1,5 +1,11 @@
-use crate::models::{course::Course, participant::Participant};
+use crate::models::{
+    course::Course,
+    participant::{Field, Participant},
+    signup_order::SignupOrder,
+};
+use chrono_tz::Tz;
 use std::fmt::Display;
+use strum::EnumProperty;
 
 #[derive(Debug)]
 pub enum TextMessage {
@@ -7,7 +13,15 @@ pub enum TextMessage {
     Cancel,
     ShowData(Participant),
     EnterDataComplete,
-    SignupResponse(Course),
+    /// The course with the timezone its times are shown in.
+    SignupResponse(Course, Tz),
+    IncompleteData(Vec<Field>),
+    RetentionWarning(i32),
+    Profiles(Vec<Participant>, i64),
+    GroupAnnouncement(Course, Tz, Vec<Option<String>>),
+    /// The participants of the course, the rule for automatic signups and whether that rule is
+    /// followed strictly.
+    Roster(Vec<Participant>, SignupOrder, bool),
 }
 
 impl Display for TextMessage {
@@ -24,7 +38,7 @@ Um loszulegen, nutze den /enter_data Befehl.
 Keine Sorge! Solltest du bei der Eingabe deiner Daten einen Fehler machen, kannst du deine Daten später ändern.
 Fahre dafür zunächst mit der Eingabe deiner Daten fort und nutze dann die in /help angezeigten Befehle, um deine Daten zu ändern.
 
-Warnung: Ich überprüfe deine Daten in keinster Weise auf Echtheit oder Korrektheit, sondern schicke diese so, wie du sie eingibts, an den UniSport weiter."
+Hinweis: Ich überprüfe deine Daten nur auf ihr Format (z.B. Postleitzahl, E-Mail-Adresse oder Matrikelnummer), nicht aber auf Echtheit. Ich schicke sie so, wie du sie eingibst, an den UniSport weiter."
             ),
             Self::Cancel => write!(f, "Aktion abgebrochen."),
             Self::ShowData(participant) => write!(
@@ -46,14 +60,134 @@ Wenn du deine Daten löschen möchtest, nutze den /delete Befehl. Alternativ kan
 Wenn Trainings anstehen, wirst du von mir benachrichtigt. Du kannst dann antworten und dich anmelden lassen.",
                 emojis::get_by_shortcode("tada").ok_or(std::fmt::Error)?
             ),
-            Self::SignupResponse(course) => write!(
+            Self::SignupResponse(course, timezone) => write!(
                 f,
                 "Heute ist Frisbee-Zeit! {}
 
-{course}
+{}
 
 Soll ich dich anmelden?",
-                emojis::get_by_shortcode("flying_disc").ok_or(std::fmt::Error)?
+                emojis::get_by_shortcode("flying_disc").ok_or(std::fmt::Error)?,
+                course.describe(*timezone)
+            ),
+            Self::IncompleteData(missing_fields) => write!(
+                f,
+                "Ich kann dich noch nicht anmelden, weil deine Daten unvollständig sind. Bitte ergänze noch:
+
+{}
+
+Danach kannst du dich mit /signup anmelden.",
+                missing_fields
+                    .iter()
+                    .map(|field| format!(
+                        "{} ({})",
+                        field
+                            .get_str("pretty")
+                            .unwrap_or("Better set that enum prop"),
+                        field
+                            .get_str("command")
+                            .unwrap_or("Better set that enum prop")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            Self::Profiles(profiles, selected_id) => write!(
+                f,
+                "Du kannst weitere Personen, z.B. Freunde oder Gastspieler, mit anmelden. Jedes Profil hat seine eigenen Daten.
+
+Deine Profile:
+{}
+
+Mit /add_profile legst du ein neues Profil an. Mit /switch_profile wählst du aus, welches Profil die /edit... Befehle, /show_data und /delete betreffen.",
+                profiles
+                    .iter()
+                    .map(|profile| if profile.id == *selected_id {
+                        format!("- {} (ausgewählt)", profile.profile_label())
+                    } else {
+                        format!("- {}", profile.profile_label())
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            Self::GroupAnnouncement(course, timezone, signups) => {
+                let names: Vec<&String> = signups.iter().flatten().collect();
+                let anonymous = signups.len() - names.len();
+                write!(
+                    f,
+                    "Heute ist Frisbee-Zeit! {}
+
+{}
+
+Wer mir privat seine Daten gegeben hat, kann sich mit dem Button unten anmelden.
+
+Dabei ({}):
+{}",
+                    emojis::get_by_shortcode("flying_disc").ok_or(std::fmt::Error)?,
+                    course.describe(*timezone),
+                    signups.len(),
+                    if signups.is_empty() {
+                        String::from("Noch niemand.")
+                    } else {
+                        names
+                            .iter()
+                            .map(|name| format!("- {name}"))
+                            .chain((anonymous > 0).then(|| format!("- {anonymous} weitere")))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                )
+            }
+            Self::Roster(participants, order, strict) => {
+                let names: Vec<String> = participants
+                    .iter()
+                    .filter_map(Participant::roster_name)
+                    .collect();
+                let anonymous = participants.len() - names.len();
+                write!(
+                    f,
+                    "Für das heutige Training sind {} Personen über mich angemeldet.",
+                    participants.len()
+                )?;
+                if !names.is_empty() {
+                    write!(
+                        f,
+                        "\n\n{}",
+                        names
+                            .iter()
+                            .map(|name| format!("- {name}"))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )?;
+                }
+                if anonymous > 0 {
+                    write!(
+                        f,
+                        "\n\n{anonymous} davon möchten nicht namentlich angezeigt werden."
+                    )?;
+                }
+                write!(
+                    f,
+                    "\n\nWer immer angemeldet werden möchte, wird nach dieser Regel angemeldet: {}",
+                    order.description()
+                )?;
+                if !strict {
+                    write!(
+                        f,
+                        " Weil mehrere Anmeldungen gleichzeitig laufen, kann sich die Reihenfolge beim UniSport leicht verschieben."
+                    )?;
+                }
+                write!(
+                    f,
+                    "\n\nMit /edit_show_in_roster legst du fest, ob dein Vorname hier angezeigt wird."
+                )
+            }
+            Self::RetentionWarning(days) => write!(
+                f,
+                "Hey! Du hast mich schon lange nicht mehr benutzt.
+
+Wenn du in den nächsten {days} Tagen nicht mit mir schreibst, lösche ich deine Daten.
+
+Wenn du weiterhin benachrichtigt werden möchtest, schicke mir einfach eine Nachricht, z.B. /show_data."
             ),
         }
     }
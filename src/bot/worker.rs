@@ -0,0 +1,109 @@
+use crate::{
+    bot::{reminders, schema::MyStorage, signup_round},
+    config::AppConfig,
+    models::job::{Job, ScheduledJob},
+    utils::http::HttpClient,
+};
+use chrono::{TimeDelta, Utc};
+use color_eyre::Result;
+use sqlx::{Pool, Postgres};
+use std::{future::Future, sync::Arc, time::Duration};
+use teloxide::prelude::*;
+
+/// How long a leased job is locked for other workers, unless the lease is extended.
+const LEASE: TimeDelta = TimeDelta::minutes(5);
+/// How often the lease of a running job is extended.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runs due jobs, for as long as the bot runs. Several bot replicas can run this at the same time.
+pub async fn run(
+    bot: Bot,
+    pool: Pool<Postgres>,
+    storage: MyStorage,
+    http: HttpClient,
+    config: Arc<AppConfig>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(10));
+    loop {
+        interval.tick().await;
+        if let Err(e) = run_due(&bot, &pool, &storage, &http, &config).await {
+            log::error!("failed to run jobs: {}", e);
+        }
+    }
+}
+
+async fn run_due(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    http: &HttpClient,
+    config: &AppConfig,
+) -> Result<()> {
+    // Jobs are leased one at a time, so that no leased job waits for others to finish.
+    while let Some(scheduled_job) = ScheduledJob::lease(pool, LEASE).await? {
+        log::info!(
+            "running job {} due at {} (attempt {}): {:?}",
+            scheduled_job.id,
+            scheduled_job.run_at,
+            scheduled_job.attempts,
+            scheduled_job.job.0
+        );
+        let job = execute(bot, pool, storage, http, config, &scheduled_job.job);
+        match with_heartbeat(pool, &scheduled_job, job).await {
+            Ok(true) => scheduled_job.complete(pool).await?,
+            Ok(false) => {
+                scheduled_job
+                    .postpone(pool, Utc::now().naive_utc() + TimeDelta::minutes(1))
+                    .await?;
+            }
+            Err(e) => {
+                log::error!("job {} failed: {}", scheduled_job.id, e);
+                scheduled_job.fail(pool, &e.to_string()).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the future of the job and extends its lease until it is done, so that long running jobs
+/// like signup rounds are not picked up by another worker.
+async fn with_heartbeat<F: Future>(
+    pool: &Pool<Postgres>,
+    scheduled_job: &ScheduledJob,
+    job: F,
+) -> F::Output {
+    tokio::pin!(job);
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick completes immediately, but the job was just leased.
+    heartbeat.tick().await;
+    loop {
+        tokio::select! {
+            output = &mut job => return output,
+            _ = heartbeat.tick() => {
+                if let Err(e) = scheduled_job.extend_lease(pool, LEASE).await {
+                    log::error!("failed to extend the lease of job {}: {}", scheduled_job.id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Executes the job. Returns `false` if it cannot be done right now and should be postponed.
+async fn execute(
+    bot: &Bot,
+    pool: &Pool<Postgres>,
+    storage: &MyStorage,
+    http: &HttpClient,
+    config: &AppConfig,
+    job: &Job,
+) -> Result<bool> {
+    match job {
+        Job::Remind {
+            participant_id,
+            course_id,
+        } => reminders::send(bot, pool, storage, config, *participant_id, *course_id).await,
+        Job::OpenSignup { course_id } => {
+            signup_round::run_when_open(bot, pool, http, config, storage, *course_id).await
+        }
+    }
+}
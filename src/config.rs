@@ -0,0 +1,434 @@
+use crate::{
+    models::signup_order::SignupOrder,
+    utils::{crypto, env::override_from_env, http},
+};
+use chrono_tz::{Europe, Tz};
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{convert::Infallible, env, fmt::Debug, fs, str::FromStr};
+use url::Url;
+
+/// All settings of the application. They are read once at startup from the TOML file at
+/// `CONFIG_FILE`, if it is set, and every setting can be overridden by its environment variable.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_url: Option<Secret>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redis_url: Option<Secret>,
+    /// The timezone of the courses, used for parsing and showing times.
+    pub timezone: Timezone,
+    pub urls: UrlConfig,
+    pub http: HttpConfig,
+    pub signup: SignupConfig,
+    pub telegram: TelegramConfig,
+    pub encryption: EncryptionConfig,
+    pub retention: RetentionConfig,
+    pub texts: TextConfig,
+}
+
+/// A value that must not show up in logs or in the output of `config check`.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Shows URLs without their password, so that host and database can still be checked.
+    fn redacted(&self) -> String {
+        let Ok(mut url) = Url::parse(&self.0) else {
+            return "***".to_string();
+        };
+        if url.password().is_none() || url.set_password(Some("***")).is_err() {
+            return "***".to_string();
+        }
+        url.to_string()
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret({})", self.redacted())
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.redacted())
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Timezone(pub Tz);
+
+impl Default for Timezone {
+    fn default() -> Self {
+        Self(Europe::Berlin)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrlConfig {
+    /// The course list of the UniSport.
+    pub courses: String,
+    /// The signup form of ISIS, which gets the course as `Kursid` query parameter.
+    pub signup: String,
+}
+
+impl Default for UrlConfig {
+    fn default() -> Self {
+        Self {
+            courses: "https://unisport.koeln/e65/e41657/e41692/k_content41702/publicGetData"
+                .to_string(),
+            signup: "https://isis.verw.uni-koeln.de/cgi/anmeldung.fcgi".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    pub user_agent: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<Secret>,
+    pub max_attempts: u32,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    pub min_host_interval_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            user_agent: http::DEFAULT_USER_AGENT.to_string(),
+            proxy_url: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 8000,
+            min_host_interval_ms: 250,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignupConfig {
+    /// How many automatic signups run at the same time.
+    pub concurrency: usize,
+    pub order: SignupOrder,
+    /// Whether the sessions of automatic signups are prepared before the booking opens.
+    pub prewarm: bool,
+    /// The pause between loading the signup form and submitting the participant's data.
+    pub form_delay_ms: u64,
+    /// The pause between submitting the participant's data and confirming the booking.
+    pub confirm_delay_ms: u64,
+}
+
+impl Default for SignupConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            order: SignupOrder::default(),
+            prewarm: true,
+            form_delay_ms: 3000,
+            confirm_delay_ms: 3000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    /// The token of the bot from the BotFather.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<Secret>,
+    /// The pause between messages to different chats, to respect the rate limits of Telegram.
+    pub message_interval_ms: u64,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            message_interval_ms: 200,
+        }
+    }
+}
+
+/// The keys for encrypting the personal data of participants.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// The base64 encoded 32 byte key that is used for encryption, e.g. generated with
+    /// `openssl rand -base64 32`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<Secret>,
+    /// Old keys that are kept during a key rotation until `encrypt-data` has re-encrypted all rows.
+    pub previous_keys: Vec<Secret>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Participants that did not interact with the bot for this long get a warning.
+    pub inactive_months: i32,
+    /// Participants that did not react to the warning within this time are deleted.
+    pub warning_days: i32,
+    /// Courses and their signups are deleted this long after they took place.
+    pub course_months: i32,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            inactive_months: 12,
+            warning_days: 14,
+            course_months: 24,
+        }
+    }
+}
+
+/// Replacements for texts of the bot. Unset texts keep the built-in ones.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextConfig {
+    /// The answer to /start.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// The answer to /cancel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel: Option<String>,
+}
+
+impl AppConfig {
+    /// Loads and validates the config.
+    pub fn load() -> Result<Self> {
+        let config = Self::read()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config without validating it.
+    pub fn read() -> Result<Self> {
+        let mut config = match env::var("CONFIG_FILE").ok().filter(|path| !path.is_empty()) {
+            Some(path) => {
+                log::info!("reading config from {path}");
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| eyre!("could not read config file {path}: {e}"))?;
+                toml::from_str(&content).map_err(|e| eyre!("invalid config file {path}: {e}"))?
+            }
+            None => Self::default(),
+        };
+        config.override_from_env()?;
+        Ok(config)
+    }
+
+    fn override_from_env(&mut self) -> Result<()> {
+        override_secret(&mut self.database_url, "DATABASE_URL");
+        override_secret(&mut self.redis_url, "REDIS_URL");
+        override_from_env(&mut self.timezone.0, "TIMEZONE")?;
+        override_from_env(&mut self.urls.courses, "COURSES_URL")?;
+        override_from_env(&mut self.urls.signup, "SIGNUP_URL")?;
+        override_from_env(&mut self.http.timeout_secs, "HTTP_TIMEOUT_SECS")?;
+        override_from_env(
+            &mut self.http.connect_timeout_secs,
+            "HTTP_CONNECT_TIMEOUT_SECS",
+        )?;
+        override_from_env(&mut self.http.user_agent, "HTTP_USER_AGENT")?;
+        override_secret(&mut self.http.proxy_url, "HTTP_PROXY_URL");
+        override_from_env(&mut self.http.max_attempts, "HTTP_MAX_ATTEMPTS")?;
+        override_from_env(
+            &mut self.http.retry_base_delay_ms,
+            "HTTP_RETRY_BASE_DELAY_MS",
+        )?;
+        override_from_env(&mut self.http.retry_max_delay_ms, "HTTP_RETRY_MAX_DELAY_MS")?;
+        override_from_env(
+            &mut self.http.min_host_interval_ms,
+            "HTTP_MIN_HOST_INTERVAL_MS",
+        )?;
+        override_from_env(&mut self.signup.concurrency, "SIGNUP_CONCURRENCY")?;
+        override_from_env(&mut self.signup.order, "SIGNUP_ORDER")?;
+        override_from_env(&mut self.signup.prewarm, "SIGNUP_PREWARM")?;
+        override_from_env(&mut self.signup.form_delay_ms, "SIGNUP_FORM_DELAY_MS")?;
+        override_from_env(&mut self.signup.confirm_delay_ms, "SIGNUP_CONFIRM_DELAY_MS")?;
+        override_secret(&mut self.telegram.token, "TELOXIDE_TOKEN");
+        override_from_env(
+            &mut self.telegram.message_interval_ms,
+            "TELEGRAM_MESSAGE_INTERVAL_MS",
+        )?;
+        override_secret(&mut self.encryption.key, "PARTICIPANT_DATA_KEY");
+        if let Ok(keys) = env::var("PARTICIPANT_DATA_PREVIOUS_KEYS") {
+            self.encryption.previous_keys = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(|key| Secret(key.to_string()))
+                .collect();
+        }
+        override_from_env(
+            &mut self.retention.inactive_months,
+            "RETENTION_INACTIVE_MONTHS",
+        )?;
+        override_from_env(&mut self.retention.warning_days, "RETENTION_WARNING_DAYS")?;
+        override_from_env(&mut self.retention.course_months, "RETENTION_COURSE_MONTHS")?;
+        Ok(())
+    }
+
+    /// Checks the whole config and reports all problems at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = vec![];
+        let mut check = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+        check(
+            self.database_url
+                .as_ref()
+                .is_some_and(|url| has_scheme(url.expose(), &["postgres", "postgresql"])),
+            "database_url (DATABASE_URL) must be a postgres:// URL",
+        );
+        check(
+            self.redis_url
+                .as_ref()
+                .is_some_and(|url| has_scheme(url.expose(), &["redis", "rediss"])),
+            "redis_url (REDIS_URL) must be a redis:// URL",
+        );
+        check(
+            has_scheme(&self.urls.courses, &["http", "https"]),
+            "urls.courses (COURSES_URL) must be an http(s) URL",
+        );
+        check(
+            has_scheme(&self.urls.signup, &["http", "https"]),
+            "urls.signup (SIGNUP_URL) must be an http(s) URL",
+        );
+        check(
+            self.http
+                .proxy_url
+                .as_ref()
+                .is_none_or(|url| Url::parse(url.expose()).is_ok()),
+            "http.proxy_url (HTTP_PROXY_URL) must be a URL",
+        );
+        check(
+            self.http.timeout_secs > 0 && self.http.connect_timeout_secs > 0,
+            "http.timeout_secs and http.connect_timeout_secs must be greater than 0",
+        );
+        check(
+            self.http.max_attempts > 0,
+            "http.max_attempts (HTTP_MAX_ATTEMPTS) must be greater than 0",
+        );
+        check(
+            self.http.retry_base_delay_ms <= self.http.retry_max_delay_ms,
+            "http.retry_base_delay_ms must not be greater than http.retry_max_delay_ms",
+        );
+        check(
+            self.signup.concurrency > 0,
+            "signup.concurrency (SIGNUP_CONCURRENCY) must be greater than 0",
+        );
+        check(
+            self.telegram
+                .token
+                .as_ref()
+                .is_some_and(|token| token.expose().contains(':')),
+            "telegram.token (TELOXIDE_TOKEN) must be set to the token from the BotFather",
+        );
+        check(
+            self.encryption
+                .key
+                .as_ref()
+                .is_some_and(|key| crypto::check_key(key.expose()).is_ok()),
+            "encryption.key (PARTICIPANT_DATA_KEY) must be a base64 encoded 32 byte key",
+        );
+        check(
+            self.encryption
+                .previous_keys
+                .iter()
+                .all(|key| crypto::check_key(key.expose()).is_ok()),
+            "encryption.previous_keys (PARTICIPANT_DATA_PREVIOUS_KEYS) must be base64 encoded 32 byte keys",
+        );
+        check(
+            self.retention.inactive_months > 0
+                && self.retention.warning_days > 0
+                && self.retention.course_months > 0,
+            "retention settings must be greater than 0",
+        );
+        check(
+            [&self.texts.start, &self.texts.cancel]
+                .into_iter()
+                .flatten()
+                .all(|text| !text.trim().is_empty()),
+            "texts must not be empty",
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(eyre!("invalid config:\n- {}", problems.join("\n- ")))
+        }
+    }
+
+    /// Loads the encryption keys, which are used by the whole process.
+    pub fn init(&self) -> Result<()> {
+        let key = self
+            .encryption
+            .key
+            .as_ref()
+            .ok_or_else(|| eyre!("encryption key not configured"))?;
+        let previous_keys: Vec<&str> = self
+            .encryption
+            .previous_keys
+            .iter()
+            .map(Secret::expose)
+            .collect();
+        crypto::init(key.expose(), &previous_keys)
+    }
+
+    pub fn database_url(&self) -> Result<&str> {
+        self.database_url
+            .as_ref()
+            .map(Secret::expose)
+            .ok_or_else(|| eyre!("database url not configured"))
+    }
+
+    pub fn redis_url(&self) -> Result<&str> {
+        self.redis_url
+            .as_ref()
+            .map(Secret::expose)
+            .ok_or_else(|| eyre!("redis url not configured"))
+    }
+
+    pub fn telegram_token(&self) -> Result<&str> {
+        self.telegram
+            .token
+            .as_ref()
+            .map(Secret::expose)
+            .ok_or_else(|| eyre!("telegram token not configured"))
+    }
+}
+
+fn override_secret(secret: &mut Option<Secret>, name: &str) {
+    if let Ok(value) = env::var(name) {
+        *secret = Some(Secret(value)).filter(|secret| !secret.0.is_empty());
+    }
+}
+
+fn has_scheme(url: &str, schemes: &[&str]) -> bool {
+    Url::parse(url).is_ok_and(|url| schemes.contains(&url.scheme()) && url.has_host())
+}
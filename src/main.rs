@@ -1,27 +1,84 @@
 extern crate pretty_env_logger;
 
 mod bot;
+mod config;
 mod models;
 mod utils;
 
 use crate::{
-    bot::{
-        keyboards,
-        message_effect::MessageEffect,
-        schema::{MyStorage, State},
-        text_messages::TextMessage,
+    bot::{schema::MyStorage, signup_round, text_messages::TextMessage},
+    config::AppConfig,
+    models::{
+        course::{BookingState, Course},
+        job::Job,
+        participant::Participant,
+        signup,
     },
-    models::{course::Course, participant::Participant, signup},
+    utils::http::HttpClient,
 };
+use chrono::NaiveDateTime;
+use clap::{Parser, Subcommand};
 use color_eyre::{eyre::eyre, Result};
-use sqlx::postgres::PgPoolOptions;
-use std::env;
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::{collections::HashMap, sync::Arc};
 use teloxide::{
     dispatching::dialogue::{serializer::Bincode, RedisStorage, Storage},
     prelude::*,
 };
 use tokio::time::{sleep, Duration};
 
+/// Telegram bot that signs up participants for the Frisbee courses of the university sports.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the Telegram bot and the job worker (default).
+    Bot,
+    /// Fetch the courses and run the signup round for today's course once the booking is open.
+    Scraper,
+    /// Apply pending database migrations.
+    Migrate,
+    /// Fetch the courses from the website of the university sports.
+    FetchCourses,
+    /// List upcoming courses.
+    ListCourses,
+    /// Announce a course, sign up automatic participants and ask everyone else.
+    Notify {
+        /// The id of the course.
+        #[arg(long)]
+        course: i64,
+    },
+    /// Sign up a participant for a course.
+    Signup {
+        /// The id of the participant (profile).
+        #[arg(long)]
+        participant: i64,
+        /// The id of the course.
+        #[arg(long)]
+        course: i64,
+    },
+    /// Delete inactive participants and old courses and warn participants before deletion.
+    Purge,
+    /// Encrypt all participant data with the current key.
+    EncryptData,
+    /// Inspect the configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective config with redacted secrets and validate it.
+    Check,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().map_or_else(
@@ -34,149 +91,292 @@ async fn main() -> Result<()> {
         },
     );
     pretty_env_logger::init_timed();
-    match std::env::args().nth(1).as_deref() {
-        Some("bot") | None => run_bot().await,
-        Some("scraper") => run_scraper().await,
-        _ => Err(eyre!("invalid argument")),
+    match Cli::parse().command.unwrap_or(Command::Bot) {
+        Command::Bot => run_bot(load_config()?).await,
+        Command::Scraper => run_scraper(load_config()?).await,
+        Command::Migrate => run_migrate(load_config()?).await,
+        Command::FetchCourses => run_fetch_courses(load_config()?).await,
+        Command::ListCourses => run_list_courses(load_config()?).await,
+        Command::Notify { course } => run_notify(load_config()?, course).await,
+        Command::Signup {
+            participant,
+            course,
+        } => run_signup(load_config()?, participant, course).await,
+        Command::Purge => run_purge(load_config()?).await,
+        Command::EncryptData => run_encrypt_data(load_config()?).await,
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => run_config_check(),
     }
 }
 
-async fn run_bot() -> Result<()> {
+/// Loads and validates the config and applies its process-wide settings.
+fn load_config() -> Result<Arc<AppConfig>> {
+    let config = AppConfig::load()?;
+    config.init()?;
+    Ok(Arc::new(config))
+}
+
+async fn connect(config: &AppConfig) -> Result<Pool<Postgres>> {
     log::info!("connecting to database");
     let pool = PgPoolOptions::new()
         .max_connections(5)
-        .connect(&env::var("DATABASE_URL")?)
+        .connect(config.database_url()?)
         .await?;
+    Ok(pool)
+}
+
+/// Prints the effective config with redacted secrets and checks it.
+fn run_config_check() -> Result<()> {
+    let config = AppConfig::read()?;
+    println!("{}", toml::to_string_pretty(&config)?);
+    config.validate()?;
+    println!("config is valid");
+    Ok(())
+}
+
+async fn run_bot(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
     sqlx::migrate!().run(&pool).await?;
 
     log::info!("starting bot");
-    bot::start(pool, env::var("REDIS_URL")?).await?;
+    let http = HttpClient::new(&config)?;
+    bot::start(pool, http, config).await?;
 
     Ok(())
 }
 
-async fn run_scraper() -> Result<()> {
-    log::info!("connecting to database");
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&env::var("DATABASE_URL")?)
-        .await?;
+async fn run_migrate(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
+    log::info!("applying migrations");
+    sqlx::migrate!().run(&pool).await?;
+    Ok(())
+}
 
+async fn run_fetch_courses(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
+    let http = HttpClient::new(&config)?;
     log::info!("fetching new courses");
-    Course::fetch(&pool).await?;
-    let Some(course_today) = Course::today(&pool).await? else {
-        log::info!("no course found for today");
-        return Ok(());
-    };
+    Course::fetch(&pool, &http).await?;
+    Ok(())
+}
 
-    if !course_today.is_signup_available().await {
-        log::info!("signup for course for today is not yet available");
-        return Ok(());
+async fn run_list_courses(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
+    for course in Course::upcoming(&pool).await? {
+        let local = |time: NaiveDateTime| {
+            time.and_utc()
+                .with_timezone(&config.timezone.0)
+                .format("%d.%m.%Y %H:%M")
+                .to_string()
+        };
+        println!(
+            "{}\t{} - {}\t{}\t{}\t{}\tbooking opens: {}",
+            course.id,
+            local(course.start_time),
+            local(course.end_time),
+            course.level,
+            course.location,
+            course.trainer,
+            course
+                .signup_opens_at
+                .map_or_else(|| "unknown".to_string(), local)
+        );
     }
+    Ok(())
+}
 
-    let bot = Bot::from_env();
-    let storage: MyStorage = RedisStorage::open(&env::var("REDIS_URL")?, Bincode)
+/// Runs the signup round for the course by hand, e.g. when the scraper failed on course day.
+/// Participants that were handled before are skipped.
+async fn run_notify(config: Arc<AppConfig>, course_id: i64) -> Result<()> {
+    let pool = connect(&config).await?;
+    let course = Course::find_by_id(&pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    let http = HttpClient::new(&config)?;
+    let state = course.booking_state(&http).await?;
+    if state != BookingState::Open {
+        log::warn!("signup for course {course_id} is not open: {state:?}");
+    }
+
+    let bot = Bot::new(config.telegram_token()?);
+    let storage: MyStorage = RedisStorage::open(config.redis_url()?, Bincode)
         .await?
         .erase();
+    signup_round::run(
+        &bot,
+        &pool,
+        &http,
+        &config,
+        &storage,
+        &course,
+        HashMap::new(),
+    )
+    .await
+}
+
+async fn run_signup(config: Arc<AppConfig>, participant_id: i64, course_id: i64) -> Result<()> {
+    let pool = connect(&config).await?;
+    let course = Course::find_by_id(&pool, course_id)
+        .await?
+        .ok_or_else(|| eyre!("course with id {} not found", course_id))?;
+    let participant = Participant::find_by_id(&pool, participant_id).await?;
+    let missing_fields = participant.missing_fields();
+    if !missing_fields.is_empty() {
+        return Err(eyre!(
+            "participant {} has incomplete data: {:?}",
+            participant.id,
+            missing_fields
+        ));
+    }
 
-    log::info!("informing participants");
-    for participant in &mut Participant::uninformed(&pool, course_today.id).await? {
-        // Only inform participants that are not currently editing their data.
-        let dialogue = storage
+    let http = HttpClient::new(&config)?;
+    log::info!(
+        "signing up participant {} for {}",
+        participant.id,
+        course.id
+    );
+    signup::attempt(&pool, &http, &participant, course.id).await?;
+    participant
+        .set_signup_status(&pool, course.id, signup::Status::SignedUp)
+        .await?;
+    log::info!("signed up participant {} for {}", participant.id, course.id);
+    Ok(())
+}
+
+async fn run_encrypt_data(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
+
+    log::info!("encrypting participant data with the current key");
+    let count = Participant::encrypt_all(&pool).await?;
+    log::info!("encrypted data of {count} participants");
+
+    Ok(())
+}
+
+async fn run_purge(config: Arc<AppConfig>) -> Result<()> {
+    let inactive_months = config.retention.inactive_months;
+    let warning_days = config.retention.warning_days;
+    let course_months = config.retention.course_months;
+
+    let pool = connect(&config).await?;
+
+    let bot = Bot::new(config.telegram_token()?);
+    let storage: MyStorage = RedisStorage::open(config.redis_url()?, Bincode)
+        .await?
+        .erase();
+
+    log::info!("deleting participants that did not react to their retention warning");
+    for participant in &mut Participant::purgeable(&pool, warning_days).await? {
+        log::info!("deleting inactive participant {}", participant.id);
+        participant.delete(&pool).await?;
+        storage
             .clone()
-            .get_dialogue(ChatId(participant.id))
+            .remove_dialogue(ChatId(participant.id))
             .await
             .map_err(|e| eyre!(e))?;
-        if let Some(state) = dialogue {
-            if !matches!(state, State::ReceiveSignupResponse(_) | State::Default) {
-                continue;
-            }
-        } else {
-            log::warn!("no dialogue found for participant {}", participant.id);
-        }
-
-        if participant.signup_always {
-            log::info!(
-                "participant {} wants to be signed up always",
-                participant.id
-            );
-            participant
-                .set_signup_status(&pool, course_today.id, signup::Status::Notified)
-                .await?;
-            match signup::perform(participant, course_today.id).await {
-                Ok(_) => {
-                    participant
-                        .set_signup_status(&pool, course_today.id, signup::Status::SignedUp)
-                        .await?;
-                }
-                Err(err) => {
-                    log::error!(
-                        "failed to sign up participant {} for {}: {}",
-                        participant.id,
-                        course_today.id,
-                        err
-                    );
-                }
-            };
-            continue;
-        }
+    }
 
-        log::info!("informing participant {}", participant.id);
+    log::info!("warning participants that were inactive for {inactive_months} months");
+    for participant in &mut Participant::inactive(&pool, inactive_months).await? {
         match bot
             .send_message(
                 ChatId(participant.id),
-                TextMessage::SignupResponse(course_today.clone()).to_string(),
+                TextMessage::RetentionWarning(warning_days).to_string(),
             )
-            .message_effect_id(MessageEffect::Fire.id())
-            .reply_markup(keyboards::signup())
             .await
         {
             Ok(_) => {
-                participant
-                    .set_signup_status(&pool, course_today.id, signup::Status::Notified)
-                    .await?;
+                participant.mark_retention_warning_sent(&pool).await?;
+                log::info!("warned participant {}", participant.id);
+            }
+            Err(e)
+                if e.to_string().contains("bot was blocked by the user")
+                    || e.to_string().contains("user is deactivated") =>
+            {
+                log::info!(
+                    "could not warn participant {}, deleting the participant and their dialogue now: {}",
+                    participant.id,
+                    e
+                );
+                participant.delete(&pool).await?;
                 storage
                     .clone()
-                    .update_dialogue(
-                        ChatId(participant.id),
-                        State::ReceiveSignupResponse(course_today.id),
-                    )
+                    .remove_dialogue(ChatId(participant.id))
                     .await
                     .map_err(|e| eyre!(e))?;
-                log::info!("successfully informed participant {}", participant.id)
             }
             Err(e) => {
-                log::error!("failed to inform participant {}: {}", participant.id, e);
-                if e.to_string().contains("bot was blocked by the user") {
-                    log::info!(
-                        "participant {} blocked the bot, deleting the participant and their dialogue now",
-                        participant.id
-                    );
-                    participant.delete(&pool).await?;
-                    storage
-                        .clone()
-                        .remove_dialogue(ChatId(participant.id))
-                        .await
-                        .map_err(|e| eyre!(e))?;
-                } else if e.to_string().contains("user is deactivated") {
-                    log::info!(
-                        "participant {} is a deactivated Telegram user, deleting the participant and their dialogue now",
-                        participant.id
-                    );
-                    participant.delete(&pool).await?;
-                    storage
-                        .clone()
-                        .remove_dialogue(ChatId(participant.id))
-                        .await
-                        .map_err(|e| eyre!(e))?;
-                }
-                continue;
+                // Temporary errors like rate limits or network problems must not delete anyone,
+                // the participant is warned in the next run instead.
+                log::error!("failed to warn participant {}: {}", participant.id, e);
             }
-        };
+        }
 
-        log::info!("sleep for 200ms to respect Telegram API rate limiting");
-        sleep(Duration::from_millis(200)).await;
+        let interval = config.telegram.message_interval_ms;
+        log::info!("sleep for {interval}ms to respect Telegram API rate limiting");
+        sleep(Duration::from_millis(interval)).await;
     }
 
+    log::info!("deleting courses and signups older than {course_months} months");
+    let count = Course::delete_older_than(&pool, course_months).await?;
+    log::info!("deleted {count} courses");
+
+    Ok(())
+}
+
+async fn run_scraper(config: Arc<AppConfig>) -> Result<()> {
+    let pool = connect(&config).await?;
+
+    let http = HttpClient::new(&config)?;
+    log::info!("fetching new courses");
+    Course::fetch(&pool, &http).await?;
+    let Some(mut course_today) = Course::today(&pool).await? else {
+        log::info!("no course found for today");
+        return Ok(());
+    };
+
+    match course_today.booking_state(&http).await? {
+        BookingState::Open => {}
+        BookingState::OpensAt(opens_at) => {
+            log::info!("signup for course for today opens at {opens_at}, scheduling it");
+            course_today
+                .set_signup_opens_at(&pool, Some(opens_at))
+                .await?;
+            Job::OpenSignup {
+                course_id: course_today.id,
+            }
+            .reschedule(&pool, opens_at - signup_round::LEAD_TIME)
+            .await?;
+            return Ok(());
+        }
+        state => {
+            log::info!("signup for course for today is not yet available: {state:?}");
+            return Ok(());
+        }
+    }
+    // The round runs here, so a scheduled one is not needed anymore.
+    Job::OpenSignup {
+        course_id: course_today.id,
+    }
+    .cancel(&pool)
+    .await?;
+
+    let bot = Bot::new(config.telegram_token()?);
+    let storage: MyStorage = RedisStorage::open(config.redis_url()?, Bincode)
+        .await?
+        .erase();
+
+    signup_round::run(
+        &bot,
+        &pool,
+        &http,
+        &config,
+        &storage,
+        &course_today,
+        HashMap::new(),
+    )
+    .await?;
+
     Ok(())
 }
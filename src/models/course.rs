@@ -1,18 +1,21 @@
-use chrono::NaiveDateTime;
-use chrono_tz::Europe;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
 use color_eyre::{
     eyre::{eyre, OptionExt},
     Result,
 };
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::Html;
 use sqlx::{Pool, Postgres};
-use std::{collections::HashMap, fmt::Display};
+use std::collections::HashMap;
 use url::Url;
 
-use crate::utils::http::request_document;
+use crate::utils::http::HttpClient;
 
-use super::signup::parse_form;
+use super::signup;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Course {
     pub id: i64,
     pub start_time: NaiveDateTime,
@@ -20,6 +23,126 @@ pub struct Course {
     pub level: String,
     pub location: String,
     pub trainer: String,
+    /// When the booking opens, if the booking page announced it.
+    pub signup_opens_at: Option<NaiveDateTime>,
+}
+
+/// The state of the booking as shown on the booking page of a course.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookingState {
+    Open,
+    /// The booking opens at the given time (UTC).
+    OpensAt(NaiveDateTime),
+    /// The booking is not open yet and the page does not say when it opens.
+    NotYetOpen,
+    /// The page says that the course is fully booked or that the booking period has ended.
+    Closed,
+    /// The page could not be read, e.g. because it is an error page of an overloaded server.
+    Unknown,
+}
+
+fn get_opening_time_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?i)\bab\s+(?:(\d{1,2})\.(\d{1,2})\.(\d{2,4})?\s*,?\s*)?(\d{1,2})[:.](\d{2})(?:\s*uhr)?\b")
+        .expect("invalid regex")
+}
+
+fn get_booking_text_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?i)buch|anmeld").expect("invalid regex")
+}
+
+fn get_closed_text_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?i)\b(ist|sind|wurde)\s+(bereits\s+|leider\s+)*(ausgebucht|abgelaufen)\b")
+        .expect("invalid regex")
+}
+
+lazy_static! {
+    static ref OPENING_TIME_REGEX: Regex = get_opening_time_regex();
+    /// Texts about the booking, the only ones that are searched for an opening time.
+    static ref BOOKING_TEXT_REGEX: Regex = get_booking_text_regex();
+    static ref CLOSED_TEXT_REGEX: Regex = get_closed_text_regex();
+}
+
+impl BookingState {
+    /// Reads the state from the booking button, e.g. `bs_btn_buchen` for an open booking or
+    /// "ab 12:00" for one that opens later, or from the messages of the ISIS signup page. Dates
+    /// without a year and bare times refer to the day of `now` in its timezone. Pages without any
+    /// known marker are `Unknown`, so that error pages never close a booking.
+    pub fn parse(document: &Html, now: DateTime<Tz>) -> Self {
+        let texts = |selector: &str| -> Vec<String> {
+            let Ok(selector) = scraper::Selector::parse(selector) else {
+                return vec![];
+            };
+            document
+                .select(&selector)
+                .map(|element| element.text().collect::<String>().trim().to_string())
+                .collect()
+        };
+        if !texts(".bs_btn_buchen").is_empty() {
+            return Self::Open;
+        }
+        let buttons = texts("[class^=bs_btn], [class*=' bs_btn']");
+        let messages: Vec<String> = document
+            .root_element()
+            .text()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+            .collect();
+        let closed_button = buttons.iter().any(|button| {
+            let button = button.to_lowercase();
+            button.contains("ausgebucht") || button.contains("abgelaufen")
+        });
+        if closed_button || messages.iter().any(|text| CLOSED_TEXT_REGEX.is_match(text)) {
+            return Self::Closed;
+        }
+        let opens_at = buttons
+            .iter()
+            .chain(
+                messages
+                    .iter()
+                    .filter(|text| BOOKING_TEXT_REGEX.is_match(text)),
+            )
+            .find_map(|text| Self::opening_time(text, now));
+        if let Some(opens_at) = opens_at {
+            return Self::OpensAt(opens_at);
+        }
+        if messages
+            .iter()
+            .any(|text| text.to_lowercase().contains("noch nicht buchbar"))
+        {
+            return Self::NotYetOpen;
+        }
+        // The ISIS signup page has no booking button, but the form for the participant's data.
+        if !texts(r#"form input[name="Vorname"]"#).is_empty() {
+            return Self::Open;
+        }
+        Self::Unknown
+    }
+
+    fn opening_time(text: &str, now: DateTime<Tz>) -> Option<NaiveDateTime> {
+        let today = now.date_naive();
+        let captures = OPENING_TIME_REGEX.captures(text)?;
+        let number = |i: usize| captures.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+        let date = match (number(1), number(2)) {
+            (Some(day), Some(month)) => {
+                let year = match number(3) {
+                    Some(year) if year < 100 => i32::try_from(2000 + year).ok()?,
+                    Some(year) => i32::try_from(year).ok()?,
+                    None => today.year(),
+                };
+                NaiveDate::from_ymd_opt(year, month, day)?
+            }
+            _ => today,
+        };
+        let time = NaiveTime::from_hms_opt(number(4)?, number(5)?, 0)?;
+        date.and_time(time)
+            .and_local_timezone(now.timezone())
+            .earliest()
+            .map(|opens_at| opens_at.naive_utc())
+    }
 }
 
 impl Course {
@@ -41,11 +164,35 @@ impl Course {
         Ok(())
     }
 
+    pub async fn set_signup_opens_at(
+        &mut self,
+        pool: &Pool<Postgres>,
+        signup_opens_at: Option<NaiveDateTime>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE courses
+            SET signup_opens_at = $2
+            WHERE id = $1
+            "#,
+            self.id,
+            signup_opens_at
+        )
+        .execute(pool)
+        .await?;
+        self.signup_opens_at = signup_opens_at;
+        Ok(())
+    }
+
+    pub fn has_started(&self) -> bool {
+        self.start_time <= Utc::now().naive_utc()
+    }
+
     pub async fn find_by_id(pool: &Pool<Postgres>, id: i64) -> Result<Option<Self>> {
         let course = sqlx::query_as!(
             Course,
             r#"
-            SELECT id, start_time, end_time, level, location, trainer
+            SELECT id, start_time, end_time, level, location, trainer, signup_opens_at
             FROM courses
             WHERE id = $1
             "#,
@@ -60,7 +207,7 @@ impl Course {
         let course = sqlx::query_as!(
             Course,
             r#"
-            SELECT id, start_time, end_time, level, location, trainer
+            SELECT id, start_time, end_time, level, location, trainer, signup_opens_at
             FROM courses
             WHERE date(start_time) = current_date
             "#
@@ -70,9 +217,87 @@ impl Course {
         Ok(course)
     }
 
-    pub async fn fetch(pool: &Pool<Postgres>) -> Result<()> {
+    pub async fn next(pool: &Pool<Postgres>) -> Result<Option<Self>> {
+        let course = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT id, start_time, end_time, level, location, trainer, signup_opens_at
+            FROM courses
+            WHERE start_time >= (now() AT TIME ZONE 'UTC')
+            ORDER BY start_time
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(course)
+    }
+
+    /// Returns the courses with signup prompts that were never answered.
+    pub async fn with_unanswered_signups(pool: &Pool<Postgres>) -> Result<Vec<Self>> {
+        let courses = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT id, start_time, end_time, level, location, trainer, signup_opens_at
+            FROM courses
+            WHERE EXISTS (
+                SELECT 1
+                FROM signups
+                WHERE signups.course_id = courses.id AND signups.status IN ('Notified', 'Queued')
+            )
+            ORDER BY start_time
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(courses)
+    }
+
+    pub async fn upcoming(pool: &Pool<Postgres>) -> Result<Vec<Self>> {
+        let courses = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT id, start_time, end_time, level, location, trainer, signup_opens_at
+            FROM courses
+            WHERE end_time >= (now() AT TIME ZONE 'UTC')
+            ORDER BY start_time
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(courses)
+    }
+
+    /// Deletes courses that started more than `months` months ago together with their signups.
+    pub async fn delete_older_than(pool: &Pool<Postgres>, months: i32) -> Result<u64> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM signups
+            USING courses
+            WHERE signups.course_id = courses.id
+                AND courses.start_time < (now() AT TIME ZONE 'UTC') - make_interval(months => $1)
+            "#,
+            months
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM courses
+            WHERE start_time < (now() AT TIME ZONE 'UTC') - make_interval(months => $1)
+            "#,
+            months
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn fetch(pool: &Pool<Postgres>, http: &HttpClient) -> Result<()> {
         log::info!("fetching courses");
-        let courses = Self::download().await?;
+        let courses = Self::download(http).await?;
         if courses.is_empty() {
             log::info!("no courses found");
             return Ok(());
@@ -95,12 +320,9 @@ impl Course {
         Ok(record.is_some())
     }
 
-    async fn download() -> Result<Vec<Self>> {
-        const COURSES_URL: &str =
-            "https://unisport.koeln/e65/e41657/e41692/k_content41702/publicGetData";
-        let client = reqwest::Client::new();
-        let request = client.get(COURSES_URL);
-        let response = request_document(request).await?;
+    async fn download(http: &HttpClient) -> Result<Vec<Self>> {
+        let request = http.client().get(&http.urls().courses);
+        let response = http.request_document(request).await?;
         let document = scraper::Html::parse_document(response.as_str());
 
         let table_header_cells_selector = scraper::Selector::parse("thead > tr:first-of-type > th")
@@ -189,7 +411,7 @@ impl Course {
                 &format!("{date} {start_time_of_day}:00"),
                 "%d.%m.%Y %H:%M:%S",
             )?
-            .and_local_timezone(Europe::Berlin)
+            .and_local_timezone(http.timezone())
             .single()
             .ok_or_eyre("could not convert to local timezone")?
             .naive_utc();
@@ -197,7 +419,7 @@ impl Course {
                 &format!("{date} {end_time_of_day}:00"),
                 "%d.%m.%Y %H:%M:%S",
             )?
-            .and_local_timezone(Europe::Berlin)
+            .and_local_timezone(http.timezone())
             .single()
             .ok_or_eyre("could not convert to local timezone")?
             .naive_utc();
@@ -221,31 +443,26 @@ impl Course {
                 level,
                 location,
                 trainer,
+                signup_opens_at: None,
             };
             courses.push(course);
         }
         Ok(courses)
     }
 
-    pub async fn is_signup_available(&self) -> bool {
-        let client = reqwest::Client::new();
-        let form_url = format!(
-            "https://isis.verw.uni-koeln.de/cgi/anmeldung.fcgi?Kursid={}",
-            self.id
-        );
-        let request = client.get(&form_url);
-        let Ok(response) = request_document(request).await else {
-            return false;
-        };
-        let document = scraper::Html::parse_document(response.as_str());
-        parse_form(&document).is_ok()
+    pub async fn booking_state(&self, http: &HttpClient) -> Result<BookingState> {
+        let request = http
+            .client()
+            .get(signup::form_url(&http.urls().signup, self.id));
+        let response = http.request_document(request).await?;
+        let document = Html::parse_document(response.as_str());
+        let now = Utc::now().with_timezone(&http.timezone());
+        Ok(BookingState::parse(&document, now))
     }
-}
 
-impl Display for Course {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
+    /// Describes the course with its times in the given timezone.
+    pub fn describe(&self, timezone: Tz) -> String {
+        format!(
             "Von: {}
 Bis: {}
 Bezeichnung: {}
@@ -253,11 +470,11 @@ Ort: {}
 Kursleiter/In: {}",
             self.start_time
                 .and_utc()
-                .with_timezone(&Europe::Berlin)
+                .with_timezone(&timezone)
                 .format("%H:%M"),
             self.end_time
                 .and_utc()
-                .with_timezone(&Europe::Berlin)
+                .with_timezone(&timezone)
                 .format("%H:%M"),
             self.level,
             self.location,
@@ -265,3 +482,123 @@ Kursleiter/In: {}",
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BookingState;
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Europe;
+    use scraper::Html;
+
+    fn state(html: &str) -> BookingState {
+        let now = Europe::Berlin
+            .with_ymd_and_hms(2026, 10, 19, 9, 0, 0)
+            .single()
+            .expect("invalid date");
+        BookingState::parse(&Html::parse_document(html), now)
+    }
+
+    fn utc(day: u32, hour: u32, minute: u32) -> BookingState {
+        BookingState::OpensAt(
+            NaiveDate::from_ymd_opt(2026, 10, day)
+                .and_then(|date| date.and_hms_opt(hour, minute, 0))
+                .expect("invalid date"),
+        )
+    }
+
+    #[test]
+    fn parses_open_button() {
+        assert_eq!(
+            state(
+                r#"<table><tr><td class="bs_sbuch"><input type="submit" value="buchen" class="bs_btn_buchen" name="BS_Kursid_123"></td></tr></table>"#
+            ),
+            BookingState::Open
+        );
+    }
+
+    #[test]
+    fn parses_opening_time() {
+        // Times are given in the timezone of the courses, which is two hours ahead of UTC in
+        // October.
+        assert_eq!(
+            state(r#"<td class="bs_sbuch"><span class="bs_btn_ab">ab 12:00</span></td>"#),
+            utc(19, 10, 0)
+        );
+    }
+
+    #[test]
+    fn parses_opening_date_and_time() {
+        assert_eq!(
+            state(
+                r#"<td class="bs_sbuch"><span class="bs_btn_ab">ab 19.10.26, 12:00 Uhr</span></td>"#
+            ),
+            utc(19, 10, 0)
+        );
+        assert_eq!(
+            state(r#"<td class="bs_sbuch"><span class="bs_btn_ab">ab 20.10. 8.30</span></td>"#),
+            utc(20, 6, 30)
+        );
+    }
+
+    #[test]
+    fn parses_not_yet_open() {
+        assert_eq!(
+            state(r#"<p>Dieser Kurs ist noch nicht buchbar.</p>"#),
+            BookingState::NotYetOpen
+        );
+    }
+
+    #[test]
+    fn parses_closed() {
+        assert_eq!(
+            state(r#"<td class="bs_sbuch"><span class="bs_btn_ausgebucht">ausgebucht</span></td>"#),
+            BookingState::Closed
+        );
+        assert_eq!(
+            state(r#"<td class="bs_sbuch"><span class="bs_btn_abgelaufen">abgelaufen</span></td>"#),
+            BookingState::Closed
+        );
+    }
+
+    #[test]
+    fn parses_isis_signup_page() {
+        assert_eq!(
+            state(
+                r#"<form action="/cgi/anmeldung.fcgi" method="post" name="bs_form">
+                    <input type="hidden" name="Kursid" value="123">
+                    <div class="bs_form_row"><label>Vorname:</label><input name="Vorname"></div>
+                    <div class="bs_form_row"><label>Name:</label><input name="Name"></div>
+                    <input type="submit" value="weiter zur Buchung">
+                </form>"#
+            ),
+            BookingState::Open
+        );
+        assert_eq!(
+            state(
+                r#"<div class="bs_meldung">Die Buchung ist erst ab 19.10.26, 12:00 Uhr möglich.</div>"#
+            ),
+            utc(19, 10, 0)
+        );
+        assert_eq!(
+            state(r#"<div class="bs_meldung">Dieses Angebot ist noch nicht buchbar.</div>"#),
+            BookingState::NotYetOpen
+        );
+        assert_eq!(
+            state(r#"<div class="bs_meldung">Das Angebot ist leider ausgebucht.</div>"#),
+            BookingState::Closed
+        );
+    }
+
+    #[test]
+    fn does_not_guess_from_unknown_pages() {
+        for html in [
+            "",
+            r#"<h1>503 Service Unavailable</h1><p>Bitte versuchen Sie es ab 12:05 erneut.</p>"#,
+            r#"<p>Training ab 18:00 in der Halle.</p><p>Zur Anmeldung</p>"#,
+            r#"<p>Wenn ein Kurs ausgebucht ist, gibt es eine Warteliste.</p>"#,
+            r#"<form action="/suche"><input name="suchbegriff"></form>"#,
+        ] {
+            assert_eq!(state(html), BookingState::Unknown, "{html}");
+        }
+    }
+}
@@ -1,6 +1,16 @@
-use strum::{EnumIter, EnumProperty, EnumString};
+use strum::{AsRefStr, EnumIter, EnumProperty, EnumString};
 
-#[derive(Debug, Clone, EnumString, EnumProperty, EnumIter, sqlx::Type)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    AsRefStr,
+    EnumString,
+    EnumProperty,
+    EnumIter,
+    sqlx::Type,
+    serde::Serialize,
+)]
 #[sqlx(type_name = "gender")]
 pub enum Gender {
     #[strum(props(pretty = "Männlich"))]
@@ -0,0 +1,152 @@
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+use sqlx::{Pool, Postgres};
+
+/// A Telegram group chat that receives the course announcements.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub chat_id: i64,
+}
+
+/// The message that announces a course in a group chat.
+#[derive(Debug, Clone)]
+pub struct Announcement {
+    pub chat_id: i64,
+    pub course_id: i64,
+    pub message_id: i32,
+}
+
+/// A signup of a group member through the button of an announcement.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AnnouncementSignup {
+    pub chat_id: i64,
+    pub course_id: i64,
+    pub display_name: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Group {
+    pub async fn create(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO group_chats (chat_id)
+            VALUES ($1)
+            ON CONFLICT DO NOTHING
+            "#,
+            self.chat_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn all(pool: &Pool<Postgres>) -> Result<Vec<Self>> {
+        let groups = sqlx::query_as!(Group, r#"SELECT chat_id FROM group_chats"#)
+            .fetch_all(pool)
+            .await?;
+        Ok(groups)
+    }
+
+    pub async fn delete(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"DELETE FROM group_chats WHERE chat_id = $1"#,
+            self.chat_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn announcement(
+        &self,
+        pool: &Pool<Postgres>,
+        course_id: i64,
+    ) -> Result<Option<Announcement>> {
+        let announcement = sqlx::query_as!(
+            Announcement,
+            r#"
+            SELECT chat_id, course_id, message_id
+            FROM announcements
+            WHERE chat_id = $1 AND course_id = $2
+            "#,
+            self.chat_id,
+            course_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(announcement)
+    }
+}
+
+impl Announcement {
+    /// Returns the announcements of the course in all group chats.
+    pub async fn for_course(pool: &Pool<Postgres>, course_id: i64) -> Result<Vec<Self>> {
+        let announcements = sqlx::query_as!(
+            Announcement,
+            r#"
+            SELECT chat_id, course_id, message_id
+            FROM announcements
+            WHERE course_id = $1
+            "#,
+            course_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(announcements)
+    }
+
+    pub async fn create(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO announcements (chat_id, course_id, message_id)
+            VALUES ($1, $2, $3)
+            "#,
+            self.chat_id,
+            self.course_id,
+            self.message_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn add_signup(
+        &self,
+        pool: &Pool<Postgres>,
+        participant_id: i64,
+        display_name: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO announcement_signups (chat_id, course_id, participant_id, display_name)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+            self.chat_id,
+            self.course_id,
+            participant_id,
+            display_name
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl AnnouncementSignup {
+    pub async fn for_participant(pool: &Pool<Postgres>, participant_id: i64) -> Result<Vec<Self>> {
+        let signups = sqlx::query_as!(
+            AnnouncementSignup,
+            r#"
+            SELECT chat_id, course_id, display_name, created_at
+            FROM announcement_signups
+            WHERE participant_id = $1
+            ORDER BY created_at
+            "#,
+            participant_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(signups)
+    }
+}
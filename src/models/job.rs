@@ -0,0 +1,175 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use color_eyre::Result;
+use sqlx::{types::Json, Pool, Postgres};
+
+/// Gives up on a job after this many failed attempts.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Work that is executed later by the worker of the bot.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Job {
+    /// Sends the signup prompt for the course to the participant again.
+    Remind { participant_id: i64, course_id: i64 },
+    /// Runs the signup round for the course as soon as its booking opens.
+    OpenSignup { course_id: i64 },
+}
+
+/// A job that is stored in the database together with its schedule.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScheduledJob {
+    pub id: i64,
+    pub job: Json<Job>,
+    pub run_at: NaiveDateTime,
+    pub attempts: i32,
+}
+
+impl Job {
+    /// Schedules the job, replacing pending jobs that are equal to it.
+    pub async fn reschedule(&self, pool: &Pool<Postgres>, run_at: NaiveDateTime) -> Result<()> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE job = $1 AND failed_at IS NULL
+            "#,
+            Json(self) as _
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO jobs (job, run_at)
+            VALUES ($1, $2)
+            "#,
+            Json(self) as _,
+            run_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Removes pending jobs that are equal to this one.
+    pub async fn cancel(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE job = $1 AND failed_at IS NULL
+            "#,
+            Json(self) as _
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl ScheduledJob {
+    /// Locks the next due job for the duration of the `lease`. Jobs that are locked by another
+    /// worker are skipped, so that every job is only run once.
+    pub async fn lease(pool: &Pool<Postgres>, lease: TimeDelta) -> Result<Option<Self>> {
+        let locked_until = Utc::now().naive_utc() + lease;
+        let job = sqlx::query_as!(
+            ScheduledJob,
+            r#"
+            UPDATE jobs
+            SET locked_until = $1, attempts = attempts + 1
+            WHERE id = (
+                SELECT id
+                FROM jobs
+                WHERE run_at <= (now() AT TIME ZONE 'UTC')
+                    AND failed_at IS NULL
+                    AND (locked_until IS NULL OR locked_until < (now() AT TIME ZONE 'UTC'))
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, job as "job: Json<Job>", run_at, attempts
+            "#,
+            locked_until
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Keeps the job locked for another `lease` while it is still running.
+    pub async fn extend_lease(&self, pool: &Pool<Postgres>, lease: TimeDelta) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET locked_until = $2
+            WHERE id = $1
+            "#,
+            self.id,
+            Utc::now().naive_utc() + lease
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the pending jobs that concern the participant, e.g. reminders.
+    pub async fn for_participant(pool: &Pool<Postgres>, participant_id: i64) -> Result<Vec<Self>> {
+        let jobs = sqlx::query_as!(
+            ScheduledJob,
+            r#"
+            SELECT id, job as "job: Json<Job>", run_at, attempts
+            FROM jobs
+            WHERE (job->>'participant_id')::bigint = $1
+            ORDER BY run_at
+            "#,
+            participant_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(jobs)
+    }
+
+    pub async fn complete(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(r#"DELETE FROM jobs WHERE id = $1"#, self.id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Runs the job again at `run_at` without counting the current attempt.
+    pub async fn postpone(&self, pool: &Pool<Postgres>, run_at: NaiveDateTime) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET run_at = $2, attempts = attempts - 1, locked_until = NULL
+            WHERE id = $1
+            "#,
+            self.id,
+            run_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the error and retries the job with exponential backoff, until it ran out of
+    /// attempts.
+    pub async fn fail(&self, pool: &Pool<Postgres>, error: &str) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        let run_at = now + TimeDelta::minutes(2_i64.pow(self.attempts.unsigned_abs()));
+        let failed_at = (self.attempts >= MAX_ATTEMPTS).then_some(now);
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET run_at = $2, failed_at = $3, last_error = $4, locked_until = NULL
+            WHERE id = $1
+            "#,
+            self.id,
+            run_at,
+            failed_at,
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
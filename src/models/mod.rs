@@ -1,5 +1,9 @@
 pub mod course;
 pub mod gender;
+pub mod group;
+pub mod job;
 pub mod participant;
+pub mod reminder;
 pub mod signup;
+pub mod signup_order;
 pub mod status;
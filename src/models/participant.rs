@@ -1,15 +1,23 @@
-use crate::models::{
-    gender::Gender,
-    signup::{self, Signup},
-    status::Status,
+use crate::{
+    models::{
+        course::Course,
+        gender::Gender,
+        signup::{self, Signup, SignupWithCourse},
+        signup_order::SignupOrder,
+        status::Status,
+    },
+    utils::crypto,
 };
+use chrono::NaiveDateTime;
 use color_eyre::Result;
 use sqlx::{Pool, Postgres};
 use strum::EnumProperty;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct Participant {
     pub id: i64,
+    pub chat_id: i64,
+    pub profile_name: Option<String>,
     pub given_name: Option<String>,
     pub last_name: Option<String>,
     pub gender: Option<Gender>,
@@ -20,25 +28,83 @@ pub struct Participant {
     pub status: Option<Status>,
     pub status_info: Option<String>,
     pub signup_always: bool,
+    pub show_in_roster: bool,
+}
+
+/// The bookkeeping columns of a participant that are not part of the profile itself.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Metadata {
+    pub selected: bool,
+    pub created_at: NaiveDateTime,
+    pub last_interaction_at: NaiveDateTime,
+    pub retention_warning_sent_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, EnumProperty)]
+pub enum Field {
+    #[strum(props(pretty = "Vorname", command = "/edit_given_name"))]
+    GivenName,
+    #[strum(props(pretty = "Nachname", command = "/edit_last_name"))]
+    LastName,
+    #[strum(props(pretty = "Geschlecht", command = "/edit_gender"))]
+    Gender,
+    #[strum(props(pretty = "Straße", command = "/edit_street"))]
+    Street,
+    #[strum(props(pretty = "Ort", command = "/edit_city"))]
+    City,
+    #[strum(props(pretty = "Telefonnummer", command = "/edit_phone"))]
+    Phone,
+    #[strum(props(pretty = "E-Mail-Adresse", command = "/edit_email"))]
+    Email,
+    #[strum(props(pretty = "Status", command = "/edit_status"))]
+    Status,
+    #[strum(props(
+        pretty = "Matrikelnummer oder dienstliche Telefonnummer",
+        command = "/edit_status_info"
+    ))]
+    StatusInfo,
+}
+
+impl Field {
+    /// Returns the field that is sent to ISIS as the form parameter `name`.
+    pub fn from_payload_name(name: &str) -> Option<Self> {
+        match name {
+            "Geschlecht" => Some(Self::Gender),
+            "Vorname" => Some(Self::GivenName),
+            "Name" => Some(Self::LastName),
+            "Strasse" => Some(Self::Street),
+            "Ort" => Some(Self::City),
+            "Statusorig" => Some(Self::Status),
+            "Matnr" | "Institut" => Some(Self::StatusInfo),
+            "Mail" => Some(Self::Email),
+            "Tel" => Some(Self::Phone),
+            _ => None,
+        }
+    }
 }
 
 impl Participant {
     pub async fn create(&self, pool: &Pool<Postgres>) -> Result<()> {
+        let encrypted = self.encrypted()?;
         sqlx::query!(
             r#"
-            INSERT INTO participants(id, given_name, last_name, gender, street, city, phone, email, status, status_info)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            INSERT INTO participants(id, chat_id, profile_name, given_name, last_name, gender, street, city, phone, email, status, status_info, selected)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             "#,
-            self.id,
-            self.given_name,
-            self.last_name,
-            self.gender.clone() as Option<Gender>,
-            self.street,
-            self.city,
-            self.phone,
-            self.email,
-            self.status.clone() as Option<Status>,
-            self.status_info,
+            encrypted.id,
+            encrypted.chat_id,
+            encrypted.profile_name,
+            encrypted.given_name,
+            encrypted.last_name,
+            encrypted.gender as Option<Gender>,
+            encrypted.street,
+            encrypted.city,
+            encrypted.phone,
+            encrypted.email,
+            encrypted.status as Option<Status>,
+            encrypted.status_info,
+            // Additional profiles are selected explicitly after they were created.
+            self.is_main_profile(),
         )
             .execute(pool)
             .await?;
@@ -49,7 +115,7 @@ impl Participant {
         let participant = sqlx::query_as!(
             Participant,
             r#"
-            SELECT id, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
             FROM participants
             WHERE id = $1
             "#,
@@ -57,31 +123,252 @@ impl Participant {
         )
         .fetch_one(pool)
         .await?;
+        participant.decrypted()
+    }
+
+    /// Creates an additional, named profile for the given chat.
+    pub async fn create_profile(
+        pool: &Pool<Postgres>,
+        chat_id: i64,
+        profile_name: String,
+    ) -> Result<Self> {
+        let id = sqlx::query_scalar!(r#"SELECT nextval('participant_profile_id_seq') as "id!""#)
+            .fetch_one(pool)
+            .await?;
+        let participant = Self {
+            id,
+            chat_id,
+            profile_name: Some(profile_name),
+            ..Default::default()
+        };
+        participant.create(pool).await?;
         Ok(participant)
     }
 
+    /// Returns the profile of the chat that is currently being edited.
+    pub async fn selected(pool: &Pool<Postgres>, chat_id: i64) -> Result<Self> {
+        let participant = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            WHERE chat_id = $1
+            ORDER BY selected DESC, profile_name NULLS FIRST
+            LIMIT 1
+            "#,
+            chat_id,
+        )
+        .fetch_one(pool)
+        .await?;
+        participant.decrypted()
+    }
+
+    /// Returns all profiles of the chat, starting with the main profile.
+    pub async fn profiles(pool: &Pool<Postgres>, chat_id: i64) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            WHERE chat_id = $1
+            ORDER BY profile_name NULLS FIRST
+            "#,
+            chat_id,
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    /// Returns the additional profiles of the chat that were notified about the course but are
+    /// not signed up yet. Profiles that are signed up automatically are left out, since the signup
+    /// round takes care of them.
+    pub async fn pending_profiles(
+        pool: &Pool<Postgres>,
+        chat_id: i64,
+        course_id: i64,
+    ) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, participants.status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            JOIN signups ON signups.participant_id = participants.id
+            WHERE participants.chat_id = $1
+                AND participants.id <> participants.chat_id
+                AND NOT participants.signup_always
+                AND signups.course_id = $2
+                AND signups.status = 'Notified'
+            ORDER BY profile_name
+            "#,
+            chat_id,
+            course_id,
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    /// Returns the participants that are signed up for the course.
+    pub async fn roster(pool: &Pool<Postgres>, course_id: i64) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, participants.status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            JOIN signups ON signups.participant_id = participants.id
+            WHERE signups.course_id = $1 AND signups.status = 'SignedUp'
+            "#,
+            course_id,
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    pub async fn set_show_in_roster(&mut self, pool: &Pool<Postgres>, show: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE participants SET show_in_roster = $1 WHERE id = $2
+            "#,
+            show,
+            self.id
+        )
+        .execute(pool)
+        .await?;
+        self.show_in_roster = show;
+        Ok(())
+    }
+
+    /// The name that is shown to other participants if they agreed to it, e.g. "Max M.".
+    pub fn roster_name(&self) -> Option<String> {
+        if !self.show_in_roster {
+            return None;
+        }
+        let given_name = self.given_name.as_ref()?;
+        Some(
+            self.last_name
+                .as_ref()
+                .and_then(|last_name| last_name.chars().next())
+                .map_or_else(
+                    || given_name.clone(),
+                    |initial| format!("{given_name} {initial}."),
+                ),
+        )
+    }
+
+    /// Makes this profile the one that is edited by the /edit_* commands.
+    pub async fn select(&self, pool: &Pool<Postgres>) -> Result<()> {
+        // Only one profile per chat may be selected, so the old selection is cleared first.
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE participants
+            SET selected = FALSE
+            WHERE chat_id = $1 AND selected AND id <> $2
+            "#,
+            self.chat_id,
+            self.id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE participants
+            SET selected = TRUE
+            WHERE id = $1
+            "#,
+            self.id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub const fn is_main_profile(&self) -> bool {
+        self.id == self.chat_id
+    }
+
+    pub fn profile_label(&self) -> &str {
+        self.profile_name.as_deref().unwrap_or("Mich")
+    }
+
+    pub async fn all(pool: &Pool<Postgres>) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
     pub async fn uninformed(pool: &Pool<Postgres>, course_id: i64) -> Result<Vec<Self>> {
         let participants = sqlx::query_as!(
             Participant,
             r#"
-            SELECT id, given_name, last_name, gender as "gender: _", street, city, phone, email, participants.status as "status: _", status_info, signup_always
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, participants.status as "status: _", status_info, signup_always, show_in_roster
             FROM participants
             WHERE NOT EXISTS (
                 SELECT 1
                 FROM signups
                 WHERE participants.id = signups.participant_id AND signups.course_id = $1
             )
+            ORDER BY chat_id, (id = chat_id)
             "#,
             course_id,
         ).fetch_all(pool).await?;
-        Ok(participants)
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    /// Returns the participants who want to be signed up always and are not yet signed up for the
+    /// course, in the order given by the rule.
+    pub async fn auto_signups(
+        pool: &Pool<Postgres>,
+        course_id: i64,
+        order: SignupOrder,
+    ) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, participants.status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            WHERE signup_always AND NOT EXISTS (
+                SELECT 1
+                FROM signups
+                WHERE participants.id = signups.participant_id AND signups.course_id = $1
+            )
+            ORDER BY
+                CASE WHEN $2 = 'Rotating' THEN (
+                    SELECT max(courses.start_time)
+                    FROM signups
+                    JOIN courses ON courses.id = signups.course_id
+                    WHERE signups.participant_id = participants.id AND signups.status = 'SignedUp'
+                ) END ASC NULLS FIRST,
+                CASE WHEN $2 = 'FewestRecentSignups' THEN (
+                    SELECT count(*)
+                    FROM signups
+                    JOIN courses ON courses.id = signups.course_id
+                    WHERE signups.participant_id = participants.id AND signups.status = 'SignedUp'
+                        AND courses.start_time > (now() AT TIME ZONE 'UTC') - interval '28 days'
+                ) END ASC,
+                created_at, id
+            "#,
+            course_id,
+            order.as_ref(),
+        ).fetch_all(pool).await?;
+        participants.into_iter().map(Self::decrypted).collect()
     }
 
     pub async fn signup(&self, pool: &Pool<Postgres>, course_id: i64) -> Result<Option<Signup>> {
         let signup = sqlx::query_as!(
             Signup,
             r#"
-            SELECT participant_id, course_id, status as "status: _"
+            SELECT participant_id, course_id, status as "status: _", prompt_message_id
             FROM signups
             WHERE participant_id = $1 AND course_id = $2
             "#,
@@ -93,7 +380,58 @@ impl Participant {
         Ok(signup)
     }
 
+    /// Returns the bookkeeping columns of this participant.
+    pub async fn metadata(&self, pool: &Pool<Postgres>) -> Result<Metadata> {
+        let metadata = sqlx::query_as!(
+            Metadata,
+            r#"
+            SELECT selected, created_at, last_interaction_at, retention_warning_sent_at
+            FROM participants
+            WHERE id = $1
+            "#,
+            self.id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(metadata)
+    }
+
+    /// Returns all signups of this participant together with the courses they belong to.
+    pub async fn signups_with_courses(
+        &self,
+        pool: &Pool<Postgres>,
+    ) -> Result<Vec<SignupWithCourse>> {
+        let records = sqlx::query!(
+            r#"
+            SELECT signups.status as "status: signup::Status", courses.id, courses.start_time, courses.end_time, courses.level, courses.location, courses.trainer, courses.signup_opens_at
+            FROM signups
+            JOIN courses ON courses.id = signups.course_id
+            WHERE signups.participant_id = $1
+            ORDER BY courses.start_time
+            "#,
+            self.id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(records
+            .into_iter()
+            .map(|record| SignupWithCourse {
+                status: record.status,
+                course: Course {
+                    id: record.id,
+                    start_time: record.start_time,
+                    end_time: record.end_time,
+                    level: record.level,
+                    location: record.location,
+                    trainer: record.trainer,
+                    signup_opens_at: record.signup_opens_at,
+                },
+            })
+            .collect())
+    }
+
     pub async fn update(&self, pool: &Pool<Postgres>) -> Result<()> {
+        let encrypted = self.encrypted()?;
         sqlx::query!(
             r#"
             UPDATE participants
@@ -108,15 +446,82 @@ impl Participant {
                 status_info = $9
             WHERE id = $10
             "#,
-            self.given_name,
-            self.last_name,
-            self.gender.clone() as Option<Gender>,
-            self.street,
-            self.city,
-            self.phone,
-            self.email,
-            self.status.clone() as Option<Status>,
-            self.status_info,
+            encrypted.given_name,
+            encrypted.last_name,
+            encrypted.gender as Option<Gender>,
+            encrypted.street,
+            encrypted.city,
+            encrypted.phone,
+            encrypted.email,
+            encrypted.status as Option<Status>,
+            encrypted.status_info,
+            encrypted.id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records that the participant interacted with the bot, which also cancels a pending
+    /// retention warning.
+    pub async fn touch(pool: &Pool<Postgres>, chat_id: i64) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE participants
+            SET last_interaction_at = (now() AT TIME ZONE 'UTC'),
+                retention_warning_sent_at = NULL
+            WHERE chat_id = $1
+            "#,
+            chat_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns main profiles without any interaction in the last `months` months that have not
+    /// been warned about their upcoming deletion yet.
+    pub async fn inactive(pool: &Pool<Postgres>, months: i32) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            WHERE id = chat_id
+                AND last_interaction_at < (now() AT TIME ZONE 'UTC') - make_interval(months => $1)
+                AND retention_warning_sent_at IS NULL
+            "#,
+            months
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    /// Returns main profiles that were warned at least `days` days ago and stayed inactive.
+    pub async fn purgeable(pool: &Pool<Postgres>, days: i32) -> Result<Vec<Self>> {
+        let participants = sqlx::query_as!(
+            Participant,
+            r#"
+            SELECT id, chat_id, profile_name, given_name, last_name, gender as "gender: _", street, city, phone, email, status as "status: _", status_info, signup_always, show_in_roster
+            FROM participants
+            WHERE id = chat_id
+                AND retention_warning_sent_at < (now() AT TIME ZONE 'UTC') - make_interval(days => $1)
+            "#,
+            days
+        )
+        .fetch_all(pool)
+        .await?;
+        participants.into_iter().map(Self::decrypted).collect()
+    }
+
+    pub async fn mark_retention_warning_sent(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE participants
+            SET retention_warning_sent_at = (now() AT TIME ZONE 'UTC')
+            WHERE id = $1
+            "#,
             self.id
         )
         .execute(pool)
@@ -124,6 +529,16 @@ impl Participant {
         Ok(())
     }
 
+    /// Re-encrypts the personal data of all participants with the current key. This encrypts
+    /// legacy plaintext rows and completes a key rotation.
+    pub async fn encrypt_all(pool: &Pool<Postgres>) -> Result<usize> {
+        let participants = Self::all(pool).await?;
+        for participant in &participants {
+            participant.update(pool).await?;
+        }
+        Ok(participants.len())
+    }
+
     pub async fn set_signup_status(
         &self,
         pool: &Pool<Postgres>,
@@ -140,25 +555,99 @@ impl Participant {
             "#,
             self.id,
             course_id,
-            status as signup::Status,
+            status.clone() as signup::Status,
         )
         .execute(pool)
         .await?;
+        // Participants that are signed up automatically never message the bot, so being signed
+        // up counts as activity for the retention rules.
+        if status == signup::Status::SignedUp {
+            Self::touch(pool, self.chat_id).await?;
+        }
         Ok(())
     }
 
-    pub async fn delete(&mut self, pool: &Pool<Postgres>) -> Result<()> {
+    /// Remembers the message that asked the participant to sign up for the course.
+    pub async fn set_signup_prompt(
+        &self,
+        pool: &Pool<Postgres>,
+        course_id: i64,
+        message_id: i32,
+    ) -> Result<()> {
         sqlx::query!(
             r#"
-            DELETE FROM participants WHERE id = $1
+            UPDATE signups
+            SET prompt_message_id = $3
+            WHERE participant_id = $1 AND course_id = $2
             "#,
-            self.id
+            self.id,
+            course_id,
+            message_id,
         )
         .execute(pool)
         .await?;
         Ok(())
     }
 
+    /// Deletes the profile. Deleting the main profile of a chat deletes all of its profiles.
+    pub async fn delete(&mut self, pool: &Pool<Postgres>) -> Result<()> {
+        let mut transaction = pool.begin().await?;
+        let deleted_selected = sqlx::query_scalar!(
+            r#"
+            DELETE FROM participants WHERE id = $1 OR chat_id = $1
+            RETURNING selected
+            "#,
+            self.id
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        // The main profile takes over if the deleted profile was the selected one.
+        if deleted_selected.contains(&true) {
+            sqlx::query!(
+                r#"
+                UPDATE participants SET selected = TRUE WHERE id = $1
+                "#,
+                self.chat_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    fn encrypted(&self) -> Result<Self> {
+        Ok(Self {
+            id: self.id,
+            chat_id: self.chat_id,
+            profile_name: self.profile_name.clone(),
+            given_name: crypto::encrypt_option(self.given_name.as_ref())?,
+            last_name: crypto::encrypt_option(self.last_name.as_ref())?,
+            gender: self.gender.clone(),
+            street: crypto::encrypt_option(self.street.as_ref())?,
+            city: crypto::encrypt_option(self.city.as_ref())?,
+            phone: crypto::encrypt_option(self.phone.as_ref())?,
+            email: crypto::encrypt_option(self.email.as_ref())?,
+            status: self.status.clone(),
+            status_info: crypto::encrypt_option(self.status_info.as_ref())?,
+            signup_always: self.signup_always,
+            show_in_roster: self.show_in_roster,
+        })
+    }
+
+    fn decrypted(self) -> Result<Self> {
+        Ok(Self {
+            given_name: crypto::decrypt_option(self.given_name)?,
+            last_name: crypto::decrypt_option(self.last_name)?,
+            street: crypto::decrypt_option(self.street)?,
+            city: crypto::decrypt_option(self.city)?,
+            phone: crypto::decrypt_option(self.phone)?,
+            email: crypto::decrypt_option(self.email)?,
+            status_info: crypto::decrypt_option(self.status_info)?,
+            ..self
+        })
+    }
+
     pub fn as_payload(&self) -> Vec<(String, String)> {
         vec![
             (
@@ -190,6 +679,39 @@ impl Participant {
         ]
     }
 
+    /// Returns the fields that are required for a signup but have not been entered yet.
+    /// The e-mail address is optional and therefore never reported as missing.
+    pub fn missing_fields(&self) -> Vec<Field> {
+        let mut missing_fields = vec![];
+        if self.given_name.is_none() {
+            missing_fields.push(Field::GivenName);
+        }
+        if self.last_name.is_none() {
+            missing_fields.push(Field::LastName);
+        }
+        if self.gender.is_none() {
+            missing_fields.push(Field::Gender);
+        }
+        if self.street.is_none() {
+            missing_fields.push(Field::Street);
+        }
+        if self.city.is_none() {
+            missing_fields.push(Field::City);
+        }
+        if self.phone.is_none() {
+            missing_fields.push(Field::Phone);
+        }
+        if self.status.is_none() {
+            missing_fields.push(Field::Status);
+        }
+        if (self.is_student() || self.is_employed_at_cgn_uni_related_thing())
+            && self.status_info.is_none()
+        {
+            missing_fields.push(Field::StatusInfo);
+        }
+        missing_fields
+    }
+
     pub fn is_student(&self) -> bool {
         self.status
             .clone()
@@ -228,6 +750,9 @@ impl std::fmt::Display for Participant {
             ),
         };
 
+        if let Some(profile_name) = &self.profile_name {
+            writeln!(f, "Profil: {profile_name} (/switch_profile)\n")?;
+        }
         write!(
             f,
             "Vorname: {} (/edit_given_name)
@@ -237,7 +762,8 @@ Straße: {} (/edit_street)
 Ort: {} (/edit_city)
 Telefonnummer: {} (/edit_phone)
 E-Mail-Adresse: {} (/edit_email)
-Status: {} (/edit_status){}",
+Status: {} (/edit_status){}
+Bei /wer anzeigen: {} (/edit_show_in_roster)",
             self.given_name
                 .as_ref()
                 .map_or("<i>leer</i>", String::as_str),
@@ -255,6 +781,7 @@ Status: {} (/edit_status){}",
                 .get_str("pretty")
                 .unwrap_or("Better set that enum prop")),
             status_info,
+            if self.show_in_roster { "Ja" } else { "Nein" },
         )
     }
 }
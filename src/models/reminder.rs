@@ -0,0 +1,26 @@
+use crate::models::course::Course;
+use chrono::{NaiveDateTime, TimeDelta};
+use strum::{AsRefStr, EnumIter, EnumProperty, EnumString};
+
+/// The choices for snoozing a signup prompt.
+#[derive(Debug, Clone, Copy, PartialEq, AsRefStr, EnumString, EnumProperty, EnumIter)]
+pub enum Delay {
+    #[strum(props(pretty = "In 1 Stunde"))]
+    OneHour,
+    #[strum(props(pretty = "In 3 Stunden"))]
+    ThreeHours,
+    #[strum(props(pretty = "1 Stunde vor Beginn"))]
+    BeforeStart,
+}
+
+impl Delay {
+    /// Returns when to remind about the course, if that is still before its start.
+    pub fn remind_at(self, course: &Course, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let remind_at = match self {
+            Self::OneHour => now + TimeDelta::hours(1),
+            Self::ThreeHours => now + TimeDelta::hours(3),
+            Self::BeforeStart => course.start_time - TimeDelta::hours(1),
+        };
+        (remind_at > now && remind_at < course.start_time).then_some(remind_at)
+    }
+}
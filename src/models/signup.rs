@@ -1,4 +1,12 @@
-use crate::{models::participant::Participant, utils::http::request_document};
+use crate::{
+    config::SignupConfig,
+    models::{
+        course::Course,
+        participant::{Field, Participant},
+    },
+    utils::http::HttpClient,
+};
+use chrono::NaiveDateTime;
 use color_eyre::{eyre::eyre, Result};
 use encoding::{all::ISO_8859_1, Encoding};
 use form_urlencoded::byte_serialize;
@@ -6,27 +14,152 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::RequestBuilder;
 use scraper::{ElementRef, Html};
+use sqlx::{Pool, Postgres};
 use strum::{EnumIter, EnumProperty, EnumString};
-use tokio::time::{sleep, Duration};
-
-const SIGNUP_URL: &str = "https://isis.verw.uni-koeln.de/cgi/anmeldung.fcgi";
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use url::Url;
 
 #[derive(Debug, Clone)]
 pub struct Signup {
-    // NOTE: These fields are only used in SQL queries right now so that clippy cannot detect their usage.
-    #[allow(dead_code)]
     pub participant_id: i64,
-    #[allow(dead_code)]
     pub course_id: i64,
     pub status: Status,
+    pub prompt_message_id: Option<i32>,
+}
+
+impl Signup {
+    /// Returns the signups of the course that were never answered.
+    pub async fn unanswered(pool: &Pool<Postgres>, course_id: i64) -> Result<Vec<Self>> {
+        let signups = sqlx::query_as!(
+            Signup,
+            r#"
+            SELECT participant_id, course_id, status as "status: _", prompt_message_id
+            FROM signups
+            WHERE course_id = $1 AND status IN ('Notified', 'Queued')
+            "#,
+            course_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(signups)
+    }
+
+    /// Marks the signup as expired, unless it was answered in the meantime.
+    pub async fn expire(&self, pool: &Pool<Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE signups
+            SET status = 'Expired'
+            WHERE participant_id = $1 AND course_id = $2 AND status IN ('Notified', 'Queued')
+            "#,
+            self.participant_id,
+            self.course_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the signup prompts that were queued because the participants were busy, limited
+    /// to the profiles of the chat with `chat_id` if given.
+    pub async fn queued(pool: &Pool<Postgres>, chat_id: Option<i64>) -> Result<Vec<Self>> {
+        let signups = sqlx::query_as!(
+            Signup,
+            r#"
+            SELECT signups.participant_id, signups.course_id, signups.status as "status: _", signups.prompt_message_id
+            FROM signups
+            JOIN courses ON signups.course_id = courses.id
+            JOIN participants ON signups.participant_id = participants.id
+            WHERE signups.status = 'Queued'
+                AND courses.start_time > (now() AT TIME ZONE 'UTC')
+                AND ($1::bigint IS NULL OR participants.chat_id = $1)
+            "#,
+            chat_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(signups)
+    }
+}
+
+/// A single try to book a course for a participant. The timestamps show in which order the
+/// participants of a course were attempted.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Attempt {
+    pub id: i64,
+    pub participant_id: i64,
+    pub course_id: i64,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub success: Option<bool>,
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, EnumString, sqlx::Type)]
+impl Attempt {
+    pub async fn start(pool: &Pool<Postgres>, participant_id: i64, course_id: i64) -> Result<Self> {
+        let attempt = sqlx::query_as!(
+            Attempt,
+            r#"
+            INSERT INTO signup_attempts (participant_id, course_id)
+            VALUES ($1, $2)
+            RETURNING id, participant_id, course_id, started_at, finished_at, success, error
+            "#,
+            participant_id,
+            course_id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(attempt)
+    }
+
+    pub async fn finish(&self, pool: &Pool<Postgres>, result: &Result<()>) -> Result<()> {
+        let error = result.as_ref().err().map(ToString::to_string);
+        sqlx::query!(
+            r#"
+            UPDATE signup_attempts
+            SET finished_at = (now() AT TIME ZONE 'UTC'), success = $2, error = $3
+            WHERE id = $1
+            "#,
+            self.id,
+            result.is_ok(),
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn for_participant(pool: &Pool<Postgres>, participant_id: i64) -> Result<Vec<Self>> {
+        let attempts = sqlx::query_as!(
+            Attempt,
+            r#"
+            SELECT id, participant_id, course_id, started_at, finished_at, success, error
+            FROM signup_attempts
+            WHERE participant_id = $1
+            ORDER BY started_at
+            "#,
+            participant_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(attempts)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, EnumString, sqlx::Type, serde::Serialize)]
 #[sqlx(type_name = "signup_status")]
 pub enum Status {
     Notified,
     SignedUp,
     Rejected,
+    Expired,
+    Queued,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SignupWithCourse {
+    pub course: Course,
+    pub status: Status,
 }
 
 #[derive(Debug, Clone, EnumIter, EnumProperty)]
@@ -35,6 +168,197 @@ pub enum Request {
     Accept,
     #[strum(props(pretty = "Heute leider nicht."))]
     Reject,
+    #[strum(props(pretty = "Später erinnern"))]
+    Remind,
+}
+
+/// Answers to the signup prompt of a chat that has additional profiles pending for the course.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Me,
+    Profile(i64),
+    All,
+}
+
+impl Selection {
+    pub fn label(&self, profiles: &[Participant]) -> String {
+        match self {
+            Self::Me => "Mich".into(),
+            Self::Profile(id) => format!(
+                "Profil {}",
+                profiles
+                    .iter()
+                    .find(|profile| profile.id == *id)
+                    .and_then(|profile| profile.profile_name.as_deref())
+                    .unwrap_or_default()
+            ),
+            Self::All if profiles.len() == 1 => "Beide".into(),
+            Self::All => "Alle".into(),
+        }
+    }
+
+    pub fn options(profiles: &[Participant]) -> Vec<Self> {
+        std::iter::once(Self::Me)
+            .chain(profiles.iter().map(|profile| Self::Profile(profile.id)))
+            .chain(std::iter::once(Self::All))
+            .collect()
+    }
+
+    pub fn parse(text: &str, profiles: &[Participant]) -> Option<Self> {
+        if profiles.is_empty() {
+            return None;
+        }
+        Self::options(profiles)
+            .into_iter()
+            .find(|selection| selection.label(profiles).eq_ignore_ascii_case(text.trim()))
+    }
+}
+
+/// The intent of a free text answer to the signup prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intent {
+    Yes,
+    No,
+    Maybe,
+    Unclear,
+}
+
+const YES_PHRASES: &[&str] = &[
+    "ja",
+    "jo",
+    "jap",
+    "jep",
+    "jup",
+    "jawohl",
+    "yes",
+    "yep",
+    "yup",
+    "klar",
+    "gerne",
+    "gern",
+    "sicher",
+    "ok",
+    "okay",
+    "safe",
+    "dabei",
+    "bin dabei",
+    "komme",
+    "ich komme",
+    "auf jeden fall",
+    "aber sowas von",
+    "warum nicht",
+    "schwanz",
+    "👍",
+    "✅",
+    "💪",
+    "🥏",
+    "🔥",
+];
+
+const NO_PHRASES: &[&str] = &[
+    "nein",
+    "ne",
+    "nee",
+    "nö",
+    "no",
+    "nope",
+    "nicht",
+    "kein",
+    "keine",
+    "leider nicht",
+    "heute nicht",
+    "heute leider nicht",
+    "komme nicht",
+    "kann nicht",
+    "keine zeit",
+    "bin raus",
+    "👎",
+    "❌",
+    "🚫",
+];
+
+const MAYBE_PHRASES: &[&str] = &[
+    "vielleicht",
+    "jain",
+    "evtl",
+    "eventuell",
+    "maybe",
+    "unsicher",
+    "mal sehen",
+    "mal schauen",
+    "weiß nicht",
+    "weiss nicht",
+    "weiß noch nicht",
+    "weiss noch nicht",
+    "🤔",
+    "🤷",
+];
+
+impl Intent {
+    /// Matches the words and emoji of `text` against known phrases, preferring the longest one
+    /// at each position, so that e.g. "warum nicht" does not count as "nicht". Unknown words are
+    /// ignored. Contradicting or missing phrases lead to `Unclear`.
+    pub fn parse(text: &str) -> Self {
+        let tokens = tokenize(text);
+        let phrases: Vec<(Self, Vec<String>)> = [
+            (Self::Yes, YES_PHRASES),
+            (Self::No, NO_PHRASES),
+            (Self::Maybe, MAYBE_PHRASES),
+        ]
+        .into_iter()
+        .flat_map(|(intent, phrases)| phrases.iter().map(move |phrase| (intent, tokenize(phrase))))
+        .collect();
+
+        let mut matches = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let longest = phrases
+                .iter()
+                .filter(|(_, phrase)| tokens[i..].starts_with(phrase))
+                .max_by_key(|(_, phrase)| phrase.len());
+            match longest {
+                Some((intent, phrase)) => {
+                    matches.push(*intent);
+                    i += phrase.len();
+                }
+                None => i += 1,
+            }
+        }
+
+        if matches.contains(&Self::Maybe) {
+            Self::Maybe
+        } else if matches.contains(&Self::Yes) && !matches.contains(&Self::No) {
+            Self::Yes
+        } else if matches.contains(&Self::No) && !matches.contains(&Self::Yes) {
+            Self::No
+        } else {
+            Self::Unclear
+        }
+    }
+}
+
+/// Splits `text` into lowercase words and single emoji. Emoji modifiers like skin tones are
+/// dropped.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    for c in text.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        let is_modifier = matches!(c, '\u{fe0f}' | '\u{200d}' | '\u{1f3fb}'..='\u{1f3ff}');
+        if !c.is_whitespace() && !c.is_ascii_punctuation() && !is_modifier {
+            tokens.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
 }
 
 fn get_success_response_regex() -> Regex {
@@ -43,38 +367,122 @@ fn get_success_response_regex() -> Regex {
         .expect("invalid regex")
 }
 
+fn get_validation_error_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?i)\b(ungültig|fehlerhaft|falsch|fehlt|bitte (geben|wählen|füllen))")
+        .expect("invalid regex")
+}
+
 lazy_static! {
     static ref SUCCESS_RESPONSE_REGEX: Regex = get_success_response_regex();
+    static ref VALIDATION_ERROR_REGEX: Regex = get_validation_error_regex();
 }
 
-pub async fn perform(participant: &Participant, course_id: i64) -> Result<()> {
-    let client = reqwest::Client::new();
-    let form_url = format!("https://isis.verw.uni-koeln.de/cgi/anmeldung.fcgi?Kursid={course_id}");
+/// The pauses between the steps of a signup.
+#[derive(Debug, Clone)]
+struct StepDelays {
+    /// Between loading the signup form and submitting the participant's data.
+    form: Duration,
+    /// Between submitting the participant's data and confirming the booking.
+    confirm: Duration,
+}
 
-    // Step 1: Get the signup page that contains session specific data
-    let request = client.get(&form_url);
-    let response = request_document(request).await?;
-    sleep(Duration::from_secs(3)).await;
-    // We need a scope here... https://github.com/causal-agent/scraper/issues/75#issuecomment-1076997293
-    let body = {
-        let document = scraper::Html::parse_document(response.as_str());
-        let form = parse_form(&document)?;
-        let mut params = params_from_form(form, false)?;
-        let participant_params = participant.as_payload();
-        for (key, value) in participant_params {
-            params.push((key, value));
+impl From<&SignupConfig> for StepDelays {
+    fn from(config: &SignupConfig) -> Self {
+        Self {
+            form: Duration::from_millis(config.form_delay_ms),
+            confirm: Duration::from_millis(config.confirm_delay_ms),
         }
-        request_body_from_params(params)?
-    };
+    }
+}
 
-    // Step 2: Submit the initial form and get the user confirmation page in response
-    let mut request = client
-        .post(SIGNUP_URL)
-        .header("Referer", &form_url)
-        .body(body);
-    request = add_headers(request);
-    let response = request_document(request).await?;
-    sleep(Duration::from_secs(3)).await;
+/// The signup of a participant for a course with its own cookie jar. A session can be pre-warmed
+/// before the booking opens, so that only the submissions are left once it does.
+pub struct Session {
+    http: HttpClient,
+    client: reqwest::Client,
+    participant: Participant,
+    course_id: i64,
+    /// The signup form and when it was loaded, if the session was pre-warmed while the booking
+    /// already showed the form.
+    form_page: Option<(String, Instant)>,
+}
+
+impl Session {
+    pub fn new(http: &HttpClient, participant: &Participant, course_id: i64) -> Result<Self> {
+        Ok(Self {
+            http: http.clone(),
+            client: http.session()?,
+            participant: participant.clone(),
+            course_id,
+            form_page: None,
+        })
+    }
+
+    pub fn participant(&self) -> &Participant {
+        &self.participant
+    }
+
+    pub fn course_id(&self) -> i64 {
+        self.course_id
+    }
+
+    /// Connects to ISIS and loads the signup page, which sets the session cookies. The page is
+    /// kept for the signup if it already contains the form.
+    pub async fn prewarm(&mut self) -> Result<()> {
+        let request = self
+            .client
+            .get(form_url(&self.http.urls().signup, self.course_id));
+        let response = self.http.request_document(request).await?;
+        let has_form = parse_form(&Html::parse_document(response.as_str())).is_ok();
+        self.form_page = has_form.then(|| (response, Instant::now()));
+        Ok(())
+    }
+
+    async fn perform(self) -> Result<()> {
+        perform(
+            &self.http,
+            &self.client,
+            &self.participant,
+            self.course_id,
+            self.form_page,
+        )
+        .await
+    }
+}
+
+pub fn form_url(signup_url: &str, course_id: i64) -> String {
+    format!("{signup_url}?Kursid={course_id}")
+}
+
+/// Signs up the participant like `perform` and records the attempt.
+pub async fn attempt(
+    pool: &Pool<Postgres>,
+    http: &HttpClient,
+    participant: &Participant,
+    course_id: i64,
+) -> Result<()> {
+    attempt_session(pool, Session::new(http, participant, course_id)?).await
+}
+
+/// Signs up the participant of the (pre-warmed) session and records the attempt.
+pub async fn attempt_session(pool: &Pool<Postgres>, session: Session) -> Result<()> {
+    let attempt = Attempt::start(pool, session.participant.id, session.course_id).await?;
+    let result = session.perform().await;
+    attempt.finish(pool, &result).await?;
+    result
+}
+
+async fn perform(
+    http: &HttpClient,
+    client: &reqwest::Client,
+    participant: &Participant,
+    course_id: i64,
+    form_page: Option<(String, Instant)>,
+) -> Result<()> {
+    let signup_url = &http.urls().signup;
+    let response = submit_personal_data(http, client, participant, course_id, form_page).await?;
+    sleep(StepDelays::from(http.signup_settings()).confirm).await;
     // We need a scope here... https://github.com/causal-agent/scraper/issues/75#issuecomment-1076997293
     let body = {
         let document = scraper::Html::parse_document(response.as_str());
@@ -87,13 +495,13 @@ pub async fn perform(participant: &Participant, course_id: i64) -> Result<()> {
 
     // Step 3: Finalize the signup
     let mut request = client
-        .post(SIGNUP_URL)
-        .header("Referer", SIGNUP_URL)
+        .post(signup_url)
+        .header("Referer", signup_url)
         .body(body);
-    request = add_headers(request);
+    request = add_headers(request, signup_url)?;
 
     // Error handling
-    match request_document(request).await {
+    match http.request_document(request).await {
         Ok(response) => {
             let html = scraper::Html::parse_document(response.as_str()).html();
             if SUCCESS_RESPONSE_REGEX.is_match(html.as_str())
@@ -115,11 +523,119 @@ pub async fn perform(participant: &Participant, course_id: i64) -> Result<()> {
                 Err(eyre!("Unbekannter Fehler."))
             }
         }
-        Err(err) => Err(err.wrap_err("Verbindungsfehler")),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Performs a dry run of a signup: The participant's data is submitted to ISIS, but the final
+/// "verbindliche Buchung" is never sent. Returns an error if ISIS rejects any of the data.
+pub async fn check(http: &HttpClient, participant: &Participant, course_id: i64) -> Result<()> {
+    let client = http.session()?;
+    let response = submit_personal_data(http, &client, participant, course_id, None).await?;
+    let document = scraper::Html::parse_document(response.as_str());
+    let errors = parse_validation_errors(&document)?;
+    if !errors.is_empty() {
+        return Err(eyre!(
+            "Der UniSport hat folgende Angaben beanstandet:\n\n{}",
+            errors.join("\n")
+        ));
     }
+    parse_form(&document).map_err(|_| eyre!("Unerwartete Antwort vom UniSport."))?;
+    Ok(())
 }
 
-pub fn parse_form(document: &Html) -> Result<ElementRef> {
+/// Step 1 and 2 of a signup: Get the signup page that contains session specific data and submit
+/// the participant's data with it. Returns the confirmation page. Step 1 is skipped if the
+/// signup page was already loaded by pre-warming the session.
+async fn submit_personal_data(
+    http: &HttpClient,
+    client: &reqwest::Client,
+    participant: &Participant,
+    course_id: i64,
+    form_page: Option<(String, Instant)>,
+) -> Result<String> {
+    let signup_url = &http.urls().signup;
+    let form_url = form_url(signup_url, course_id);
+
+    // Step 1: Get the signup page that contains session specific data
+    let (response, loaded_at) = match form_page {
+        Some(form_page) => form_page,
+        None => {
+            let request = client.get(&form_url);
+            (http.request_document(request).await?, Instant::now())
+        }
+    };
+    sleep_until(loaded_at + StepDelays::from(http.signup_settings()).form).await;
+    // We need a scope here... https://github.com/causal-agent/scraper/issues/75#issuecomment-1076997293
+    let body = {
+        let document = scraper::Html::parse_document(response.as_str());
+        let form = parse_form(&document)?;
+        let mut params = params_from_form(form, false)?;
+        let participant_params = participant.as_payload();
+        for (key, value) in participant_params {
+            params.push((key, value));
+        }
+        request_body_from_params(params)?
+    };
+
+    // Step 2: Submit the initial form and get the user confirmation page in response
+    let mut request = client
+        .post(signup_url)
+        .header("Referer", &form_url)
+        .body(body);
+    request = add_headers(request, signup_url)?;
+    let response = http.request_document(request).await?;
+    {
+        let document = scraper::Html::parse_document(response.as_str());
+        // Pages without the form are handled by the callers.
+        if let Ok(form) = parse_form(&document) {
+            check_round_trip(form, &participant.as_payload())?;
+        }
+    }
+    Ok(response)
+}
+
+/// ISIS marks rejected form fields with the `warn` class and shows the form again. If the form is
+/// shown again without such markers, the lines of the form that read like error messages are
+/// returned instead, or a generic error if there are none.
+fn parse_validation_errors(document: &Html) -> Result<Vec<String>> {
+    let selector = |selector: &str| {
+        scraper::Selector::parse(selector).map_err(|e| eyre!("scraper error: {e}"))
+    };
+    let errors: Vec<String> = document
+        .select(&selector(".warn")?)
+        .map(|element| {
+            element
+                .text()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|text| !text.is_empty())
+        .collect();
+    if !errors.is_empty() {
+        return Ok(errors);
+    }
+    // The confirmation page only repeats the data in hidden or read-only fields.
+    let data_entry = selector(r#"input[name="Vorname"]:not([type="hidden"]):not([readonly])"#)?;
+    if document.select(&data_entry).next().is_none() {
+        return Ok(vec![]);
+    }
+    let form = parse_form(document)?;
+    let errors: Vec<String> = form
+        .text()
+        .map(str::trim)
+        .filter(|text| VALIDATION_ERROR_REGEX.is_match(text))
+        .map(str::to_string)
+        .collect();
+    if errors.is_empty() {
+        return Ok(vec!["Die Angaben wurden nicht angenommen.".into()]);
+    }
+    Ok(errors)
+}
+
+pub fn parse_form(document: &Html) -> Result<ElementRef<'_>> {
     let form_selector =
         scraper::Selector::parse("form").map_err(|e| eyre!("scraper error: {e}"))?;
     let form_element = document
@@ -129,11 +645,15 @@ pub fn parse_form(document: &Html) -> Result<ElementRef> {
     Ok(form_element)
 }
 
-fn add_headers(request: RequestBuilder) -> RequestBuilder {
-    request
+fn add_headers(request: RequestBuilder, signup_url: &str) -> Result<RequestBuilder> {
+    let url = Url::parse(signup_url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| eyre!("no host in signup url {signup_url}"))?;
+    Ok(request
         .header("Content-Type", "application/x-www-form-urlencoded")
-        .header("Host", "isis.verw.uni-koeln.de")
-        .header("Origin", "https://isis.verw.uni-koeln.de")
+        .header("Host", host)
+        .header("Origin", url.origin().ascii_serialization()))
 }
 
 fn params_from_form(form: ElementRef<'_>, keep_user_params: bool) -> Result<Vec<(String, String)>> {
@@ -174,13 +694,241 @@ fn request_body_from_params(mut params: Vec<(String, String)>) -> Result<String>
 }
 
 fn encode_params(params: &mut [(String, String)]) -> Result<()> {
-    for (_, value) in params.iter_mut() {
-        *value = byte_serialize(
-            &ISO_8859_1
-                .encode(value, encoding::EncoderTrap::Strict)
-                .map_err(|e| eyre!(e))?,
-        )
-        .collect();
+    for (name, value) in params.iter_mut() {
+        let encoded = ISO_8859_1
+            .encode(value, encoding::EncoderTrap::Strict)
+            .map_err(|_| encoding_error(name, value))?;
+        *value = byte_serialize(&encoded).collect();
+    }
+    Ok(())
+}
+
+/// Names the first character of the value that cannot be encoded in ISO-8859-1 and the field that
+/// contains it.
+fn encoding_error(name: &str, value: &str) -> color_eyre::Report {
+    let c = value
+        .chars()
+        .find(|c| u32::from(*c) > 0xFF)
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+    let Some(field) = Field::from_payload_name(name) else {
+        return eyre!(
+            "Das Zeichen \"{c}\" im Formularfeld {name} kann ich leider nicht an den UniSport weiterschicken."
+        );
+    };
+    let pretty = field
+        .get_str("pretty")
+        .unwrap_or_else(|| panic!("Better set that enum prop"));
+    let command = field
+        .get_str("command")
+        .unwrap_or_else(|| panic!("Better set that enum prop"));
+    eyre!(
+        "Das Zeichen \"{c}\" in deinem Feld {pretty} kann ich leider nicht an den UniSport weiterschicken. Ändere es mit {command}."
+    )
+}
+
+/// Compares the values on the confirmation page with the submitted ones. Differences that involve
+/// non-ASCII characters mean that the data got mangled by a wrong encoding on the way.
+fn check_round_trip(form: ElementRef<'_>, submitted: &[(String, String)]) -> Result<()> {
+    let normalize = |value: &str| {
+        value
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let received = params_from_form(form, true)?;
+    for (name, sent) in submitted {
+        let Some((_, got)) = received.iter().find(|(other, _)| other == name) else {
+            continue;
+        };
+        if normalize(sent) == normalize(got) || (sent.is_ascii() && got.is_ascii()) {
+            continue;
+        }
+        let field = Field::from_payload_name(name).map_or(name.as_str(), |field| {
+            field
+                .get_str("pretty")
+                .unwrap_or_else(|| panic!("Better set that enum prop"))
+        });
+        return Err(eyre!(
+            "Der UniSport hat dein Feld {field} als \"{got}\" statt \"{sent}\" übernommen. Ich habe die Anmeldung deshalb abgebrochen. Bitte melde dich bei den Entwicklern."
+        ));
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_round_trip, encoding_error, parse_validation_errors, request_body_from_params, Intent,
+    };
+    use scraper::{Html, Selector};
+
+    #[test]
+    fn parses_yes() {
+        for text in [
+            "Ja",
+            "ja!",
+            "Yes",
+            "klar",
+            "Bin dabei",
+            "Aber sowas von!",
+            "ja, warum nicht",
+            "Schwanz",
+            "👍",
+            "👍🏽",
+        ] {
+            assert_eq!(Intent::parse(text), Intent::Yes, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_no() {
+        for text in [
+            "Nein",
+            "nee",
+            "Heute leider nicht.",
+            "komme nicht",
+            "keine Zeit",
+            "👎",
+        ] {
+            assert_eq!(Intent::parse(text), Intent::No, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_maybe() {
+        for text in [
+            "vielleicht",
+            "Jain",
+            "weiß noch nicht",
+            "ja, vielleicht",
+            "🤔",
+        ] {
+            assert_eq!(Intent::parse(text), Intent::Maybe, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_unclear() {
+        for text in ["", "Hallo", "ja nein", "👍👎", "Jasmin", "yesterday", "😂"] {
+            assert_eq!(Intent::parse(text), Intent::Unclear, "{text}");
+        }
+    }
+
+    fn validation_errors(html: &str) -> Vec<String> {
+        parse_validation_errors(&Html::parse_document(html)).expect("invalid selector")
+    }
+
+    #[test]
+    fn parses_marked_validation_errors() {
+        let html = r#"<form action="/cgi/anmeldung.fcgi" method="post">
+            <div class="bs_form_row"><label>Vorname:</label><input name="Vorname" value="Max"></div>
+            <div class="bs_form_row warn"><label>E-Mail:</label><input name="Email" value="max@">
+                <span>Die E-Mail-Adresse ist ungültig.</span></div>
+        </form>"#;
+        assert_eq!(
+            validation_errors(html),
+            vec!["E-Mail: Die E-Mail-Adresse ist ungültig."]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_form_text() {
+        let html = r#"<form action="/cgi/anmeldung.fcgi" method="post">
+            <p>Bitte geben Sie eine gültige Postleitzahl ein.</p>
+            <label>Vorname:</label><input name="Vorname" value="Max">
+            <label>Ort:</label><input name="Ort" value="Köln">
+        </form>"#;
+        assert_eq!(
+            validation_errors(html),
+            vec!["Bitte geben Sie eine gültige Postleitzahl ein."]
+        );
+        let html = r#"<form><input name="Vorname" value="Max"></form>"#;
+        assert_eq!(
+            validation_errors(html),
+            vec!["Die Angaben wurden nicht angenommen."]
+        );
+    }
+
+    #[test]
+    fn accepts_the_confirmation_page() {
+        let html = r#"<form action="/cgi/anmeldung.fcgi" method="post">
+            <p>Bitte überprüfen Sie Ihre Angaben.</p>
+            <input type="hidden" name="Vorname" value="Max">
+            <input type="submit" value="verbindliche Buchung">
+        </form>"#;
+        assert!(validation_errors(html).is_empty());
+    }
+
+    #[test]
+    fn names_the_character_and_field_that_cannot_be_encoded() {
+        let err = encoding_error("Vorname", "Łukasz").to_string();
+        assert!(err.contains("\"Ł\""), "{err}");
+        assert!(err.contains("Vorname"), "{err}");
+        assert!(err.contains("/edit_given_name"), "{err}");
+        let err = encoding_error("Kommentar", "Grüße ✌").to_string();
+        assert!(err.contains("\"✌\""), "{err}");
+        assert!(err.contains("Formularfeld Kommentar"), "{err}");
+    }
+
+    #[test]
+    fn rejects_values_outside_of_latin_1() {
+        let params = vec![
+            (String::from("Vorname"), String::from("Jürgen")),
+            (String::from("Name"), String::from("Dvořák")),
+        ];
+        let err = request_body_from_params(params)
+            .expect_err("encoded ř")
+            .to_string();
+        assert!(err.contains("\"ř\""), "{err}");
+        assert!(err.contains("Nachname"), "{err}");
+        let params = vec![(String::from("Vorname"), String::from("Jürgen"))];
+        assert_eq!(
+            request_body_from_params(params).expect("failed to encode ü"),
+            "Vorname=J%FCrgen"
+        );
+    }
+
+    fn check_confirmation(html: &str, submitted: &[(&str, &str)]) -> color_eyre::Result<()> {
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("form").expect("invalid selector");
+        let form = document.select(&selector).next().expect("no form");
+        let submitted: Vec<(String, String)> = submitted
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        check_round_trip(form, &submitted)
+    }
+
+    #[test]
+    fn accepts_values_that_survived_the_round_trip() {
+        let html = r#"<form>
+            <input type="hidden" name="Vorname" value="Jürgen">
+            <input type="hidden" name="Ort" value="50937  köln">
+            <!-- ASCII-only differences are no encoding problem. -->
+            <input type="hidden" name="Tel" value="0221 12345">
+        </form>"#;
+        check_confirmation(
+            html,
+            &[
+                ("Vorname", "Jürgen"),
+                ("Ort", "50937 Köln"),
+                ("Tel", "0221 123456"),
+                ("Mail", "max@example.com"),
+            ],
+        )
+        .expect("rejected the confirmation");
+    }
+
+    #[test]
+    fn rejects_mangled_values() {
+        let html = r#"<form>
+            <input type="hidden" name="Vorname" value="JÃ¼rgen">
+        </form>"#;
+        let err = check_confirmation(html, &[("Vorname", "Jürgen")])
+            .expect_err("accepted a mangled value")
+            .to_string();
+        assert!(err.contains("Vorname"), "{err}");
+        assert!(err.contains("\"JÃ¼rgen\" statt \"Jürgen\""), "{err}");
+    }
+}
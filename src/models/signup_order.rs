@@ -0,0 +1,44 @@
+use strum::{AsRefStr, EnumProperty, EnumString};
+
+/// The rule deciding in which order participants who want to be signed up always are signed up,
+/// which matters when there are fewer places than participants.
+///
+/// The signups start in this order, but up to `signup.concurrency` of them run at the same time,
+/// so their final booking requests can reach the website in a slightly different order. The order
+/// is therefore best effort; a concurrency of 1 makes it strict.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    AsRefStr,
+    EnumString,
+    EnumProperty,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub enum SignupOrder {
+    /// Participants who registered with the bot first are signed up first.
+    #[default]
+    #[strum(props(pretty = "Wer sich zuerst beim Bot angemeldet hat, wird zuerst angemeldet."))]
+    SignupDate,
+    /// Participants who were not signed up for the longest time are signed up first, so everyone
+    /// gets to the front in turn.
+    #[strum(props(
+        pretty = "Wer am längsten nicht mehr angemeldet wurde, wird zuerst angemeldet. So kommt jede Person reihum nach vorne."
+    ))]
+    Rotating,
+    /// Participants with the fewest signups in the last 28 days are signed up first.
+    #[strum(props(
+        pretty = "Wer in den letzten 28 Tagen am seltensten angemeldet wurde, wird zuerst angemeldet."
+    ))]
+    FewestRecentSignups,
+}
+
+impl SignupOrder {
+    pub fn description(self) -> &'static str {
+        self.get_str("pretty")
+            .unwrap_or_else(|| panic!("Better set that enum prop"))
+    }
+}
@@ -1,6 +1,16 @@
-use strum::{EnumIter, EnumProperty, EnumString};
+use strum::{AsRefStr, EnumIter, EnumProperty, EnumString};
 
-#[derive(Debug, Clone, PartialEq, EnumString, EnumProperty, EnumIter, sqlx::Type)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    AsRefStr,
+    EnumString,
+    EnumProperty,
+    EnumIter,
+    sqlx::Type,
+    serde::Serialize,
+)]
 #[sqlx(type_name = "participant_status")]
 pub enum Status {
     #[strum(props(pretty = "Stud. Uni Köln"))]
@@ -0,0 +1,211 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use color_eyre::{
+    eyre::{eyre, OptionExt},
+    Result,
+};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// Prefix of encrypted values. Values without this prefix are treated as legacy plaintext.
+const PREFIX: &str = "enc:v1:";
+const NONCE_LENGTH: usize = 24;
+
+static KEYRING: OnceLock<Keyring> = OnceLock::new();
+
+struct Key {
+    id: String,
+    cipher: XChaCha20Poly1305,
+}
+
+impl Key {
+    fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| eyre!("invalid base64 in encryption key: {e}"))?;
+        if bytes.len() != 32 {
+            return Err(eyre!(
+                "encryption key must be 32 bytes long, got {}",
+                bytes.len()
+            ));
+        }
+        let id = Sha256::digest(&bytes)
+            .iter()
+            .take(4)
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        let cipher = XChaCha20Poly1305::new_from_slice(&bytes).map_err(|e| eyre!(e))?;
+        Ok(Self { id, cipher })
+    }
+}
+
+struct Keyring {
+    current: Key,
+    previous: Vec<Key>,
+}
+
+impl Keyring {
+    fn find(&self, id: &str) -> Option<&Key> {
+        std::iter::once(&self.current)
+            .chain(self.previous.iter())
+            .find(|key| key.id == id)
+    }
+
+    fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let key = &self.current;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = key
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| eyre!("encryption failed: {e}"))?;
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!("{PREFIX}{}:{}", key.id, STANDARD.encode(payload)))
+    }
+
+    fn decrypt(&self, value: &str) -> Result<String> {
+        let Some(encrypted) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let (key_id, payload) = encrypted
+            .split_once(':')
+            .ok_or_eyre("malformed encrypted value")?;
+        let key = self
+            .find(key_id)
+            .ok_or_else(|| eyre!("no encryption key with id {key_id} configured"))?;
+        let payload = STANDARD.decode(payload)?;
+        if payload.len() < NONCE_LENGTH {
+            return Err(eyre!("malformed encrypted value"));
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        let plaintext = key
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|e| eyre!("decryption failed: {e}"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+/// Loads the base64 encoded encryption keys. The `current` key is used for encryption, the
+/// `previous` keys are kept during a key rotation until `encrypt-data` has re-encrypted all rows.
+pub fn init(current: &str, previous: &[&str]) -> Result<()> {
+    let current = Key::from_base64(current)?;
+    let previous = previous
+        .iter()
+        .map(|key| Key::from_base64(key))
+        .collect::<Result<Vec<_>>>()?;
+    KEYRING
+        .set(Keyring { current, previous })
+        .map_err(|_| eyre!("encryption keys already initialized"))
+}
+
+/// Checks that the base64 encoded key can be used for encryption.
+pub fn check_key(encoded: &str) -> Result<()> {
+    Key::from_base64(encoded).map(|_| ())
+}
+
+fn keyring() -> Result<&'static Keyring> {
+    KEYRING.get().ok_or_eyre("encryption keys not initialized")
+}
+
+pub fn encrypt(plaintext: &str) -> Result<String> {
+    keyring()?.encrypt(plaintext)
+}
+
+/// Decrypts a value with the key it was encrypted with. Legacy plaintext is returned as it is.
+pub fn decrypt(value: &str) -> Result<String> {
+    keyring()?.decrypt(value)
+}
+
+pub fn encrypt_option(plaintext: Option<&String>) -> Result<Option<String>> {
+    plaintext.map(|value| encrypt(value)).transpose()
+}
+
+pub fn decrypt_option(value: Option<String>) -> Result<Option<String>> {
+    value.map(|value| decrypt(&value)).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, Keyring, NONCE_LENGTH, PREFIX, STANDARD};
+    use base64::Engine;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+    const OTHER_KEY: &str = "Hx4dHBsaGRgXFhUUExIREA8ODQwLCgkIBwYFBAMCAQA=";
+
+    fn keyring(current: &str, previous: &[&str]) -> Keyring {
+        Keyring {
+            current: Key::from_base64(current).expect("invalid key"),
+            previous: previous
+                .iter()
+                .map(|key| Key::from_base64(key).expect("invalid key"))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let keyring = keyring(KEY, &[]);
+        let encrypted = keyring.encrypt("Jürgen Müller").expect("encryption failed");
+        assert!(encrypted.starts_with(PREFIX), "{encrypted}");
+        assert!(!encrypted.contains("Jürgen"), "{encrypted}");
+        assert_eq!(
+            keyring.decrypt(&encrypted).expect("decryption failed"),
+            "Jürgen Müller"
+        );
+        // Every value gets its own nonce.
+        assert_ne!(
+            keyring.encrypt("Jürgen Müller").expect("encryption failed"),
+            encrypted
+        );
+    }
+
+    #[test]
+    fn decrypts_with_previous_key_after_rotation() {
+        let encrypted = keyring(OTHER_KEY, &[])
+            .encrypt("0221 123456")
+            .expect("encryption failed");
+        let rotated = keyring(KEY, &[OTHER_KEY]);
+        assert_eq!(
+            rotated.decrypt(&encrypted).expect("decryption failed"),
+            "0221 123456"
+        );
+        assert!(keyring(KEY, &[]).decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn passes_legacy_plaintext_through() {
+        assert_eq!(
+            keyring(KEY, &[])
+                .decrypt("Musterstr. 1")
+                .expect("decryption failed"),
+            "Musterstr. 1"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_values() {
+        let keyring = keyring(KEY, &[]);
+        let encrypted = keyring
+            .encrypt("max@example.com")
+            .expect("encryption failed");
+        let (prefix, payload) = encrypted.rsplit_once(':').expect("malformed value");
+        let mut payload = STANDARD.decode(payload).expect("invalid base64");
+        payload[NONCE_LENGTH] ^= 0x01;
+        let tampered = format!("{prefix}:{}", STANDARD.encode(payload));
+        let err = keyring
+            .decrypt(&tampered)
+            .expect_err("tampered value was decrypted");
+        assert!(err.to_string().starts_with("decryption failed"), "{err}");
+        assert!(keyring.decrypt(&format!("{PREFIX}nokey")).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(Key::from_base64("kein base64").is_err());
+        assert!(Key::from_base64("AAECAwQFBgcICQoLDA0ODw==").is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use color_eyre::{eyre::eyre, Result};
+use std::{env, fmt::Display, str::FromStr};
+
+/// Replaces `value` with the parsed environment variable `name`, if it is set.
+pub fn override_from_env<T: FromStr>(value: &mut T, name: &str) -> Result<()>
+where
+    T::Err: Display,
+{
+    if let Ok(raw) = env::var(name) {
+        *value = raw
+            .parse()
+            .map_err(|e| eyre!("invalid value for {name}: {e}"))?;
+    }
+    Ok(())
+}
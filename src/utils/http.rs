@@ -1,17 +1,398 @@
-use color_eyre::{eyre::eyre, Result};
-use reqwest::RequestBuilder;
-
-pub async fn request_document(builder: RequestBuilder) -> Result<String> {
-    let response = builder.send().await.map_err(|err| {
-        log::error!("request error: {err}");
-        eyre!("Verbindungsfehler: {err}")
-    })?;
-    if response.status() != 200 {
-        log::error!("got response with code {}", response.status());
-        return Err(eyre!(
-            "Server hat mit Code {} geantwortet",
-            response.status()
-        ));
-    }
-    Ok(response.text().await?)
+use crate::config::{AppConfig, HttpConfig, SignupConfig, UrlConfig};
+use chrono_tz::Tz;
+use color_eyre::Result;
+use encoding::{all::WINDOWS_1252, label::encoding_from_whatwg_label, DecoderTrap, Encoding};
+use lazy_static::lazy_static;
+use rand::Rng;
+use regex::Regex;
+use reqwest::{
+    cookie::Jar, header::CONTENT_TYPE, Client, ClientBuilder, Proxy, RequestBuilder, Response,
+    StatusCode,
+};
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (Anmelde-Bot der Frisbee-Gruppe)"
+);
+
+/// Creates the clients for all requests to the UniSport. Cloning it is cheap and shares the
+/// connection pool and the request slots of the hosts.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    settings: HttpConfig,
+    policy: RequestPolicy,
+    /// The earliest time the next request to each host may start.
+    next_host_slot: Arc<Mutex<HashMap<String, Instant>>>,
+    urls: UrlConfig,
+    signup: SignupConfig,
+    /// The timezone of the times on the UniSport pages.
+    timezone: Tz,
+}
+
+impl HttpClient {
+    pub fn new(config: &AppConfig) -> Result<Self> {
+        let client = builder(&config.http)?.build()?;
+        Ok(Self {
+            client,
+            settings: config.http.clone(),
+            policy: RequestPolicy::from(&config.http),
+            next_host_slot: Arc::new(Mutex::new(HashMap::new())),
+            urls: config.urls.clone(),
+            signup: config.signup.clone(),
+            timezone: config.timezone.0,
+        })
+    }
+
+    /// The shared client for requests that do not belong to a session.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Returns a client with its own cookie jar for the requests of a single signup.
+    ///
+    /// reqwest ties the cookie store to the client and has no cookie jar per request, so every
+    /// signup needs its own client and cannot use the shared connection pool. Handling the cookies
+    /// by hand on the shared client would lose those that ISIS sets on redirects, which reqwest
+    /// follows internally. A signup only opens one or two connections, and pre-warmed sessions
+    /// open them before the booking does, so the extra handshakes do not delay the signups.
+    pub fn session(&self) -> Result<Client> {
+        Ok(builder(&self.settings)?
+            .cookie_provider(Arc::new(Jar::default()))
+            .build()?)
+    }
+
+    pub fn urls(&self) -> &UrlConfig {
+        &self.urls
+    }
+
+    pub fn signup_settings(&self) -> &SignupConfig {
+        &self.signup
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Sends the request and returns the body of the response. Requests that fail because the
+    /// server is unreachable or, for idempotent requests, overloaded are retried with jittered
+    /// exponential backoff.
+    pub async fn request_document(&self, builder: RequestBuilder) -> Result<String, Error> {
+        let policy = &self.policy;
+        let idempotent = builder
+            .try_clone()
+            .and_then(|builder| builder.build().ok())
+            .is_some_and(|request| request.method().is_idempotent());
+        let mut attempt = 1;
+        loop {
+            // Requests with streaming bodies cannot be cloned and are only sent once.
+            let Some(current) = builder.try_clone() else {
+                return text(self.send(builder).await?).await;
+            };
+            let err = match self.send(current).await {
+                Ok(response) => return text(response).await,
+                Err(err) => err,
+            };
+            if attempt >= policy.max_attempts || !err.is_retryable(idempotent) {
+                log::error!("request failed after {attempt} attempt(s): {err}");
+                return Err(err);
+            }
+            let delay = policy.delay(attempt);
+            log::warn!("request failed in attempt {attempt}, retrying in {delay:?}: {err}");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, Error> {
+        let (client, request) = builder.build_split();
+        let request = request.map_err(Error::Network)?;
+        if let Some(host) = request.url().host_str() {
+            self.wait_for_host(host).await;
+        }
+        let response = client.execute(request).await.map_err(Error::Network)?;
+        if !response.status().is_success() {
+            return Err(Error::Status(response.status()));
+        }
+        Ok(response)
+    }
+
+    /// Reserves the next free slot for a request to the host and waits until it starts.
+    async fn wait_for_host(&self, host: &str) {
+        let interval = self.policy.min_host_interval;
+        if interval.is_zero() {
+            return;
+        }
+        let slot = {
+            let mut slots = self.next_host_slot.lock().await;
+            let now = Instant::now();
+            let slot = slots.get(host).map_or(now, |next| (*next).max(now));
+            slots.insert(host.to_string(), slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+fn builder(settings: &HttpConfig) -> Result<ClientBuilder> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        .user_agent(&settings.user_agent);
+    if let Some(proxy) = &settings.proxy_url {
+        builder = builder.proxy(Proxy::all(proxy.expose())?);
+    }
+    Ok(builder)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or no response was received.
+    Network(reqwest::Error),
+    /// The server answered with an unsuccessful status code.
+    Status(StatusCode),
+    /// The body of the response could not be read.
+    Decode(reqwest::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "Verbindungsfehler: {err}"),
+            Self::Status(status) => write!(f, "Server hat mit Code {status} geantwortet"),
+            Self::Decode(err) => write!(f, "Antwort konnte nicht gelesen werden: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Only retry errors where the request did not reach the server or the server is overloaded.
+    /// An error status does not tell whether the server acted on the request, so requests that
+    /// must not be repeated, like the booking, are only retried if they were never sent.
+    fn is_retryable(&self, idempotent: bool) -> bool {
+        match self {
+            Self::Network(err) => err.is_connect(),
+            Self::Status(status) => {
+                idempotent && (status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS)
+            }
+            Self::Decode(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Minimum time between the starts of two requests to the same host, so that concurrent
+    /// signups do not flood the UniSport.
+    pub min_host_interval: Duration,
+}
+
+impl From<&HttpConfig> for RequestPolicy {
+    fn from(config: &HttpConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+            min_host_interval: Duration::from_millis(config.min_host_interval_ms),
+        }
+    }
+}
+
+impl RequestPolicy {
+    /// Returns a random delay of up to `base_delay * 2^(attempt - 1)`, capped at `max_delay`.
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        exponential.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+fn get_meta_charset_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([\w:.-]+)"#).expect("invalid regex")
+}
+
+lazy_static! {
+    static ref META_CHARSET_REGEX: Regex = get_meta_charset_regex();
+}
+
+fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn charset_from_meta(body: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&body[..body.len().min(2048)]);
+    META_CHARSET_REGEX
+        .captures(&head)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Decodes the body with the charset declared in the `Content-Type` header or in a `<meta>` tag
+/// of the document. Without a declaration, UTF-8 is tried before falling back to Windows-1252,
+/// the superset of ISO-8859-1 that browsers use.
+fn decode(body: &[u8], content_type: Option<&str>) -> String {
+    let charset = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| charset_from_meta(body));
+    if let Some(encoding) = charset.as_deref().and_then(encoding_from_whatwg_label) {
+        return encoding
+            .decode(body, DecoderTrap::Replace)
+            .unwrap_or_else(|e| e.into_owned());
+    }
+    if let Some(charset) = charset {
+        log::warn!("unknown charset {charset}, guessing the encoding instead");
+    }
+    String::from_utf8(body.to_vec()).unwrap_or_else(|_| {
+        WINDOWS_1252
+            .decode(body, DecoderTrap::Replace)
+            .unwrap_or_else(|e| e.into_owned())
+    })
+}
+
+async fn text(response: Response) -> Result<String, Error> {
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.bytes().await.map_err(Error::Decode)?;
+    Ok(decode(&body, content_type.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{charset_from_content_type, charset_from_meta, decode, Error, RequestPolicy};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    fn policy() -> RequestPolicy {
+        RequestPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_millis(8000),
+            min_host_interval: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn delays_with_jitter_up_to_the_cap() {
+        let policy = policy();
+        for _ in 0..100 {
+            assert!(policy.delay(1) <= Duration::from_millis(500));
+            assert!(policy.delay(3) <= Duration::from_millis(2000));
+            assert!(policy.delay(10) <= Duration::from_millis(8000));
+            assert!(policy.delay(u32::MAX) <= Duration::from_millis(8000));
+        }
+        // The jitter spreads the delays over the whole range instead of always using the maximum.
+        let delays: Vec<Duration> = (0..100).map(|_| policy.delay(10)).collect();
+        assert!(delays
+            .iter()
+            .any(|delay| *delay < Duration::from_millis(4000)));
+        assert!(delays
+            .iter()
+            .any(|delay| *delay > Duration::from_millis(4000)));
+    }
+
+    #[test]
+    fn retries_server_errors_only_for_idempotent_requests() {
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+        ] {
+            assert!(Error::Status(status).is_retryable(true), "{status}");
+            assert!(!Error::Status(status).is_retryable(false), "{status}");
+        }
+        for status in [StatusCode::NOT_FOUND, StatusCode::BAD_REQUEST] {
+            assert!(!Error::Status(status).is_retryable(true), "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_connection_errors_also_for_other_requests() {
+        // Nothing listens on the port after the listener is dropped, so the connection is refused.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("no free port")
+            .port();
+        let err = reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{port}"))
+            .send()
+            .await
+            .expect_err("request to a closed port succeeded");
+        assert!(Error::Network(err).is_retryable(false));
+    }
+
+    #[test]
+    fn reads_charset_from_content_type() {
+        assert_eq!(
+            charset_from_content_type("text/html; charset=ISO-8859-1").as_deref(),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(
+            charset_from_content_type(r#"text/html;Charset="utf-8""#).as_deref(),
+            Some("utf-8")
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+    }
+
+    #[test]
+    fn reads_charset_from_meta() {
+        assert_eq!(
+            charset_from_meta(br#"<html><head><meta charset="iso-8859-1"></head>"#).as_deref(),
+            Some("iso-8859-1")
+        );
+        assert_eq!(
+            charset_from_meta(
+                br#"<meta http-equiv="Content-Type" content="text/html; charset=windows-1252">"#
+            )
+            .as_deref(),
+            Some("windows-1252")
+        );
+        assert_eq!(charset_from_meta(b"<html><head></head>"), None);
+    }
+
+    #[test]
+    fn decodes_declared_charset() {
+        // "Köln" in ISO-8859-1
+        let body = b"K\xf6ln";
+        assert_eq!(decode(body, Some("text/html; charset=ISO-8859-1")), "Köln");
+        let body = b"<meta charset=\"iso-8859-1\">K\xf6ln";
+        assert_eq!(decode(body, None), "<meta charset=\"iso-8859-1\">Köln");
+        // The header wins over the document.
+        let body = "<meta charset=\"iso-8859-1\">Köln".as_bytes();
+        assert_eq!(
+            decode(body, Some("text/html; charset=utf-8")),
+            "<meta charset=\"iso-8859-1\">Köln"
+        );
+    }
+
+    #[test]
+    fn guesses_undeclared_charset() {
+        assert_eq!(decode("Köln".as_bytes(), None), "Köln");
+        assert_eq!(decode(b"K\xf6ln", None), "Köln");
+        // Windows-1252 has typographic quotes where ISO-8859-1 has control characters.
+        assert_eq!(decode(b"\x84Hallo\x93", Some("text/html")), "„Hallo“");
+        assert_eq!(
+            decode(b"K\xf6ln", Some("text/html; charset=unknown")),
+            "Köln"
+        );
+    }
 }
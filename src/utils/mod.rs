@@ -1 +1,4 @@
+pub mod crypto;
+pub mod env;
 pub mod http;
+pub mod validation;
@@ -0,0 +1,237 @@
+use crate::models::status::Status;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum ValidationError {
+    Empty,
+    NotLatin1(char),
+    ControlCharacter,
+    InvalidEmail,
+    InvalidCity,
+    InvalidPhone,
+    InvalidMatrikelnummer,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Die Eingabe darf nicht leer sein."),
+            Self::NotLatin1(c) => write!(
+                f,
+                "Das Zeichen \"{c}\" kann ich leider nicht an den UniSport weiterschicken."
+            ),
+            Self::ControlCharacter => write!(
+                f,
+                "Die Eingabe darf keine Zeilenumbrüche oder anderen Steuerzeichen enthalten."
+            ),
+            Self::InvalidEmail => write!(
+                f,
+                "Das sieht nicht nach einer gültigen E-Mail-Adresse aus.\n\nBeispiel: max.mustermann@example.com"
+            ),
+            Self::InvalidCity => write!(
+                f,
+                "Bitte gib eine fünfstellige Postleitzahl gefolgt von deinem Ort ein.\n\nBeispiel: 50678 Köln"
+            ),
+            Self::InvalidPhone => write!(
+                f,
+                "Eine Telefonnummer darf nur aus Ziffern, Leerzeichen und den Zeichen + / - ( ) bestehen und muss 6 bis 15 Ziffern enthalten."
+            ),
+            Self::InvalidMatrikelnummer => write!(
+                f,
+                "Das sieht nicht nach einer gültigen Matrikelnummer aus."
+            ),
+        }
+    }
+}
+
+fn get_email_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"^[^\s@]+@[^\s@]+\.[^\s@]+$").expect("invalid regex")
+}
+
+fn get_city_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"^\d{5}\s+\S.*$").expect("invalid regex")
+}
+
+fn get_phone_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"^\+?[\d\s/()-]+$").expect("invalid regex")
+}
+
+fn get_matrikelnummer_uni_koeln_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"^\d{7}$").expect("invalid regex")
+}
+
+fn get_matrikelnummer_regex() -> Regex {
+    #[allow(clippy::expect_used)]
+    Regex::new(r"^\d{5,10}$").expect("invalid regex")
+}
+
+lazy_static! {
+    static ref EMAIL_REGEX: Regex = get_email_regex();
+    static ref CITY_REGEX: Regex = get_city_regex();
+    static ref PHONE_REGEX: Regex = get_phone_regex();
+    static ref MATRIKELNUMMER_UNI_KOELN_REGEX: Regex = get_matrikelnummer_uni_koeln_regex();
+    static ref MATRIKELNUMMER_REGEX: Regex = get_matrikelnummer_regex();
+}
+
+/// Checks that the input is non-empty and only contains characters that can be sent to ISIS,
+/// which expects ISO-8859-1 encoded form data. Control characters like line breaks are rejected
+/// as well, since they would end up in a single line form field.
+pub fn text(input: &str) -> Result<String, ValidationError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ValidationError::Empty);
+    }
+    if let Some(c) = input.chars().find(|c| u32::from(*c) > 0xFF) {
+        return Err(ValidationError::NotLatin1(c));
+    }
+    if input.chars().any(char::is_control) {
+        return Err(ValidationError::ControlCharacter);
+    }
+    Ok(input.to_string())
+}
+
+pub fn email(input: &str) -> Result<String, ValidationError> {
+    let input = text(input)?;
+    if !EMAIL_REGEX.is_match(&input) {
+        return Err(ValidationError::InvalidEmail);
+    }
+    Ok(input)
+}
+
+pub fn city(input: &str) -> Result<String, ValidationError> {
+    let input = text(input)?;
+    if !CITY_REGEX.is_match(&input) {
+        return Err(ValidationError::InvalidCity);
+    }
+    Ok(input)
+}
+
+pub fn phone(input: &str) -> Result<String, ValidationError> {
+    let input = text(input)?;
+    let digits = input.chars().filter(char::is_ascii_digit).count();
+    if !PHONE_REGEX.is_match(&input) || !(6..=15).contains(&digits) {
+        return Err(ValidationError::InvalidPhone);
+    }
+    Ok(input)
+}
+
+/// Validates the status related info, which is the Matrikelnummer for students and the work phone
+/// number for employees.
+pub fn status_info(status: &Status, input: &str) -> Result<String, ValidationError> {
+    if status.is_employed_at_cgn_uni_related_thing() {
+        return phone(input);
+    }
+    let input = text(input)?;
+    let regex: &Regex = match status {
+        Status::StudentUniKoeln => &MATRIKELNUMMER_UNI_KOELN_REGEX,
+        _ => &MATRIKELNUMMER_REGEX,
+    };
+    if status.is_student() && !regex.is_match(&input) {
+        return Err(ValidationError::InvalidMatrikelnummer);
+    }
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{city, email, phone, status_info, text, ValidationError};
+    use crate::models::status::Status;
+
+    #[test]
+    fn trims_text() {
+        assert_eq!(text("  Jürgen ").ok(), Some("Jürgen".to_string()));
+        assert!(matches!(text("   "), Err(ValidationError::Empty)));
+    }
+
+    #[test]
+    fn rejects_characters_outside_latin1() {
+        assert!(matches!(
+            text("Łukasz"),
+            Err(ValidationError::NotLatin1('Ł'))
+        ));
+        assert!(matches!(
+            text("Max 🥏"),
+            Err(ValidationError::NotLatin1('🥏'))
+        ));
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        for input in [
+            "Max\nMustermann",
+            "Max\tMustermann",
+            "Max\u{7f}M.",
+            "Max\u{85}M.",
+        ] {
+            assert!(
+                matches!(text(input), Err(ValidationError::ControlCharacter)),
+                "{input:?}"
+            );
+        }
+        assert!(matches!(
+            email("max\r\n@example.com"),
+            Err(ValidationError::ControlCharacter)
+        ));
+    }
+
+    #[test]
+    fn validates_email() {
+        assert!(email("max.mustermann@example.com").is_ok());
+        for input in ["max", "max@example", "max @example.com", "@example.com"] {
+            assert!(
+                matches!(email(input), Err(ValidationError::InvalidEmail)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn validates_city() {
+        assert!(city("50678 Köln").is_ok());
+        for input in ["Köln", "5067 Köln", "50678", "50678Köln"] {
+            assert!(
+                matches!(city(input), Err(ValidationError::InvalidCity)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn validates_phone() {
+        for input in ["0221 470-0", "+49 (221) 123456", "0221/123456"] {
+            assert!(phone(input).is_ok(), "{input}");
+        }
+        for input in ["12345", "0221 123 456 789 012 34", "0221 abc"] {
+            assert!(
+                matches!(phone(input), Err(ValidationError::InvalidPhone)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn validates_status_info() {
+        assert!(status_info(&Status::StudentUniKoeln, "1234567").is_ok());
+        assert!(matches!(
+            status_info(&Status::StudentUniKoeln, "123456"),
+            Err(ValidationError::InvalidMatrikelnummer)
+        ));
+        assert!(status_info(&Status::StudentTHKoeln, "12345").is_ok());
+        assert!(matches!(
+            status_info(&Status::StudentTHKoeln, "12a45"),
+            Err(ValidationError::InvalidMatrikelnummer)
+        ));
+        assert!(status_info(&Status::BeschaeftigteUniKlinikKoeln, "0221 478-0").is_ok());
+        assert!(matches!(
+            status_info(&Status::BeschaeftigteUniKlinikKoeln, "Station 3"),
+            Err(ValidationError::InvalidPhone)
+        ));
+        assert!(status_info(&Status::Gast, "irgendwas").is_ok());
+    }
+}